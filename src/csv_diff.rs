@@ -0,0 +1,38 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CsvDiff {
+    // relative to the root of the repository
+    pub path: String,
+    pub added: Vec<CsvRow>,
+    pub removed: Vec<CsvRow>,
+    pub changed: Vec<CsvRowChange>,
+    // why the file could not be compared, such as a duplicate key,
+    // the row changes are empty then
+    pub error: Option<String>,
+}
+
+impl CsvDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.error.is_none()
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CsvRow {
+    // value of the key column
+    pub key: String,
+    // column name to value
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CsvRowChange {
+    pub key: String,
+    pub old: BTreeMap<String, String>,
+    pub new: BTreeMap<String, String>,
+}
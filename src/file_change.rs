@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FileChange {
+    // relative to the root of the repository
+    pub path: String,
+    // set for renames
+    pub old_path: Option<String>,
+    pub kind: FileChangeKind,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    TypeChanged,
+}
@@ -0,0 +1,17 @@
+use crate::Result;
+
+// converts file contents between the working tree and the repository,
+// like a git clean/smudge filter but run in process
+pub trait Filter: Send + Sync {
+    // working tree contents to the contents stored on add
+    fn clean(&self, path: &str, content: &[u8]) -> Result<Vec<u8>>;
+
+    // what clean returns, without side effects such as keeping a copy of
+    // the contents, to compare working tree files with what is staged
+    fn clean_for_compare(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        self.clean(path, content)
+    }
+
+    // stored contents to the contents written on checkout
+    fn smudge(&self, path: &str, content: &[u8]) -> Result<Vec<u8>>;
+}
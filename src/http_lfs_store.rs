@@ -0,0 +1,338 @@
+use crate::lfs_store::LfsStore;
+use crate::{Origin, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+
+const MEDIA_TYPE: &str = "application/vnd.git-lfs+json";
+
+// an LFS server speaking the batch API, such as the one a git host serves
+// next to each repository; the token is sent as a bearer token to the
+// server, transfers use the headers the server returns with each action
+pub struct HttpLfsStore {
+    pub url: String,
+    pub token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    operation: &'a str,
+    transfers: Vec<&'a str>,
+    objects: Vec<ObjectId<'a>>,
+}
+
+#[derive(Serialize)]
+struct ObjectId<'a> {
+    oid: &'a str,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    objects: Vec<ObjectResponse>,
+}
+
+#[derive(Deserialize)]
+struct ObjectResponse {
+    // missing if the server needs nothing done, such as an upload of an
+    // object it already has
+    #[serde(default)]
+    actions: HashMap<String, Action>,
+    error: Option<ObjectError>,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    href: String,
+    #[serde(default)]
+    header: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ObjectError {
+    code: u16,
+    message: String,
+}
+
+impl HttpLfsStore {
+    pub fn new(url: &str, token: Option<&str>) -> Self {
+        HttpLfsStore {
+            url: url.trim_end_matches('/').to_string(),
+            token: token.map(|s| s.to_string()),
+        }
+    }
+
+    // the endpoint git-lfs uses for a remote without lfs.url,
+    // https://host/owner/name.git/info/lfs
+    pub fn for_origin(origin: &Origin) -> Result<Self> {
+        if !origin.url.starts_with("https://") && !origin.url.starts_with("http://") {
+            return Err(crate::Error::from_message(format!(
+                "no LFS endpoint for `{}`, set lfs.url",
+                origin.url
+            )));
+        }
+
+        let url = origin.url.trim_end_matches('/');
+
+        let url = match url.ends_with(".git") {
+            true => format!("{}/info/lfs", url),
+            false => format!("{}.git/info/lfs", url),
+        };
+
+        Ok(HttpLfsStore::new(&url, origin.token.as_deref()))
+    }
+
+    // asks the server what to do for one object
+    fn batch(&self, operation: &str, oid: &str, size: u64) -> Result<ObjectResponse> {
+        let mut request = ureq::post(&format!("{}/objects/batch", self.url))
+            .set("Accept", MEDIA_TYPE)
+            .set("Content-Type", MEDIA_TYPE);
+
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+
+        let response: BatchResponse = request
+            .send_json(BatchRequest {
+                operation,
+                transfers: vec!["basic"],
+                objects: vec![ObjectId { oid, size }],
+            })
+            .map_err(request_error)?
+            .into_json()?;
+
+        let object = response.objects.into_iter().next().ok_or_else(|| {
+            crate::Error::from_message(format!("LFS server did not answer for `{}`", oid))
+        })?;
+
+        match object.error {
+            Some(error) if error.code != 404 => Err(crate::Error::from_message(format!(
+                "LFS object `{}`: {} ({})",
+                oid, error.message, error.code
+            ))),
+            _ => Ok(object),
+        }
+    }
+}
+
+impl LfsStore for HttpLfsStore {
+    fn contains(&self, oid: &str, size: u64) -> Result<bool> {
+        let object = self.batch("download", oid, size)?;
+
+        Ok(object.error.is_none() && object.actions.contains_key("download"))
+    }
+
+    fn upload(&self, oid: &str, content: &[u8]) -> Result<()> {
+        let size = content.len() as u64;
+
+        let object = self.batch("upload", oid, size)?;
+
+        let action = match object.actions.get("upload") {
+            Some(action) => action,
+            None => return Ok(()),
+        };
+
+        action_request("PUT", action)
+            .set("Content-Type", "application/octet-stream")
+            .send_bytes(content)
+            .map_err(request_error)?;
+
+        if let Some(action) = object.actions.get("verify") {
+            action_request("POST", action)
+                .set("Accept", MEDIA_TYPE)
+                .set("Content-Type", MEDIA_TYPE)
+                .send_json(ObjectId { oid, size })
+                .map_err(request_error)?;
+        }
+
+        Ok(())
+    }
+
+    fn download(&self, oid: &str, size: u64) -> Result<Vec<u8>> {
+        let object = self.batch("download", oid, size)?;
+
+        let action = object.actions.get("download").ok_or_else(|| {
+            crate::Error::from_message(format!("LFS server does not have `{}`", oid))
+        })?;
+
+        let mut content = vec![];
+
+        action_request("GET", action)
+            .call()
+            .map_err(request_error)?
+            .into_reader()
+            .read_to_end(&mut content)?;
+
+        Ok(content)
+    }
+}
+
+fn action_request(method: &str, action: &Action) -> ureq::Request {
+    let mut request = ureq::request(method, &action.href);
+
+    for (name, value) in &action.header {
+        request = request.set(name, value);
+    }
+
+    request
+}
+
+fn request_error(err: ureq::Error) -> crate::Error {
+    match err {
+        ureq::Error::Status(code, response) => crate::Error::from_message(format!(
+            "LFS server responded {} for {}",
+            code,
+            response.get_url()
+        )),
+        ureq::Error::Transport(transport) => crate::Error::from_message(transport),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HttpLfsStore;
+    use crate::{LfsStore, Origin, Result};
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    // answers batch requests with actions on itself and keeps objects in
+    // memory, one request per connection
+    fn serve(listener: TcpListener, objects: Arc<Mutex<HashMap<String, Vec<u8>>>>) {
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut line = String::new();
+
+            reader.read_line(&mut line).unwrap();
+
+            let mut parts = line.split_whitespace();
+
+            let (method, path) = (parts.next().unwrap().to_string(), parts.next().unwrap().to_string());
+
+            let mut length = 0;
+
+            loop {
+                let mut header = String::new();
+
+                reader.read_line(&mut header).unwrap();
+
+                if header.trim().is_empty() {
+                    break;
+                }
+
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; length];
+
+            reader.read_exact(&mut body).unwrap();
+
+            let mut objects = objects.lock().unwrap();
+
+            let (status, response) = match (method.as_str(), path.as_str()) {
+                ("POST", "/info/lfs/objects/batch") => {
+                    let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+                    let object = &request["objects"][0];
+
+                    let oid = object["oid"].as_str().unwrap();
+
+                    let action = serde_json::json!({ "href": format!("{}/objects/{}", url, oid) });
+
+                    let answer = match (request["operation"].as_str().unwrap(), objects.contains_key(oid)) {
+                        ("upload", false) => serde_json::json!({ "oid": oid, "actions": { "upload": action } }),
+                        ("upload", true) => serde_json::json!({ "oid": oid }),
+                        (_, true) => serde_json::json!({ "oid": oid, "actions": { "download": action } }),
+                        (_, false) => serde_json::json!({ "oid": oid, "error": { "code": 404, "message": "not found" } }),
+                    };
+
+                    ("200 OK", serde_json::to_vec(&serde_json::json!({ "objects": [answer] })).unwrap())
+                }
+                ("PUT", _) => {
+                    objects.insert(path.trim_start_matches("/objects/").to_string(), body);
+
+                    ("200 OK", vec![])
+                }
+                ("GET", _) => match objects.get(path.trim_start_matches("/objects/")) {
+                    Some(object) => ("200 OK", object.clone()),
+                    None => ("404 Not Found", vec![]),
+                },
+                _ => ("404 Not Found", vec![]),
+            };
+
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                response.len()
+            )
+            .unwrap();
+
+            stream.write_all(&response).unwrap();
+        }
+    }
+
+    #[test]
+    fn http_lfs_store_test() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let objects = Arc::new(Mutex::new(HashMap::new()));
+
+        let served = objects.clone();
+
+        std::thread::spawn(move || serve(listener, served));
+
+        let store = HttpLfsStore::new(&format!("{}/info/lfs/", url), None);
+
+        let content = b"photo".to_vec();
+
+        let oid = format!("{:x}", Sha256::digest(&content));
+
+        assert!(!store.contains(&oid, 5)?);
+
+        assert!(store.download(&oid, 5).is_err());
+
+        store.upload(&oid, &content)?;
+
+        assert!(objects.lock().unwrap()[&oid] == content);
+
+        // the server already has it, so nothing is sent
+        store.upload(&oid, &content)?;
+
+        assert!(store.contains(&oid, 5)?);
+
+        assert!(store.download(&oid, 5)? == content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn for_origin_test() -> Result<()> {
+        let store = HttpLfsStore::for_origin(&Origin::new("https://example.com/owner/name", Some("token")))?;
+
+        assert!(store.url == "https://example.com/owner/name.git/info/lfs");
+
+        assert!(store.token.as_deref() == Some("token"));
+
+        let store = HttpLfsStore::for_origin(&Origin::new("https://example.com/owner/name.git", None))?;
+
+        assert!(store.url == "https://example.com/owner/name.git/info/lfs");
+
+        assert!(HttpLfsStore::for_origin(&Origin::new("git@example.com:owner/name.git", None)).is_err());
+
+        Ok(())
+    }
+}
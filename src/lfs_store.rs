@@ -0,0 +1,55 @@
+use crate::Result;
+use std::fs;
+use std::path::PathBuf;
+
+// where LFS objects are uploaded on push and downloaded on checkout,
+// keyed by the sha256 of their contents; sizes are those in the pointers
+pub trait LfsStore: Send + Sync {
+    fn contains(&self, oid: &str, size: u64) -> Result<bool>;
+
+    fn upload(&self, oid: &str, content: &[u8]) -> Result<()>;
+
+    fn download(&self, oid: &str, size: u64) -> Result<Vec<u8>>;
+}
+
+// an LFS store in a local directory, laid out like .git/lfs/objects
+pub struct FileSystemLfsStore {
+    pub path: PathBuf,
+}
+
+impl FileSystemLfsStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileSystemLfsStore { path: path.into() }
+    }
+
+    fn object_path(&self, oid: &str) -> Result<PathBuf> {
+        if oid.len() < 5 || !oid.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(crate::Error::from_message(format!(
+                "invalid LFS object id `{}`",
+                oid
+            )));
+        }
+
+        Ok(self.path.join(&oid[0..2]).join(&oid[2..4]).join(oid))
+    }
+}
+
+impl LfsStore for FileSystemLfsStore {
+    fn contains(&self, oid: &str, _size: u64) -> Result<bool> {
+        Ok(self.object_path(oid)?.is_file())
+    }
+
+    fn upload(&self, oid: &str, content: &[u8]) -> Result<()> {
+        let path = self.object_path(oid)?;
+
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    fn download(&self, oid: &str, _size: u64) -> Result<Vec<u8>> {
+        Ok(fs::read(self.object_path(oid)?)?)
+    }
+}
@@ -1,17 +1,43 @@
 // MIT Andrew Hickman <andrew.hickman1@sky.com>
 #![allow(warnings)]
 mod credentials_state;
+mod csv_diff;
 mod error;
+mod file_change;
+mod filter;
 mod head_status;
+mod http_lfs_store;
+mod lfs_store;
+mod merge_driver;
 mod origin;
+mod reflog_entry;
 mod repository;
+mod repository_state;
 mod repository_status;
 mod resolve;
 mod settings;
+mod stash;
+mod tag;
+mod tree_entry;
 mod working_tree_status;
 
+pub use csv_diff::{CsvDiff, CsvRow, CsvRowChange};
 pub use error::{Error, Result};
+pub use file_change::{FileChange, FileChangeKind};
+pub use filter::Filter;
+pub use http_lfs_store::HttpLfsStore;
+pub use lfs_store::{FileSystemLfsStore, LfsStore};
+pub use merge_driver::{MergeDriver, MergeOutcome};
 pub use origin::Origin;
-pub use repository::{PullOutcome, Repository};
-pub use resolve::Resolve;
-pub use settings::Settings;
+pub use reflog_entry::ReflogEntry;
+pub use repository::{
+    CloneOptions, CommitBuilder, CsvDiffOptions, CsvMergeDriver, FetchOptions, FetchOutcome, PullOptions, PullOutcome,
+    PullStrategy, PushOptions, PushOutcome, Recovery, Repository, ResetMode, RestoreSource,
+    Transaction, UpdatedRef,
+};
+pub use repository_state::RepositoryState;
+pub use resolve::{Resolve, ResolveConflict};
+pub use settings::{EncryptionSettings, Settings};
+pub use stash::{Stash, StashOutcome};
+pub use tag::Tag;
+pub use tree_entry::{TreeEntry, TreeEntryKind};
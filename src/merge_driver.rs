@@ -0,0 +1,21 @@
+use crate::Result;
+
+// merges a file both sides changed, consulted for each conflicted path
+// before leaving libgit2's textual merge conflicted
+pub trait MergeDriver: Send + Sync {
+    // base is None if the file was added on both sides
+    fn merge(
+        &self,
+        path: &str,
+        base: Option<&[u8]>,
+        ours: &[u8],
+        theirs: &[u8],
+    ) -> Result<MergeOutcome>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MergeOutcome {
+    Merged(Vec<u8>),
+    // what is left conflicted, such as csv keys, empty if the whole file is
+    Conflicted(Vec<String>),
+}
@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ReflogEntry {
+    // position like `main@{1}`, 0 is the current value
+    pub index: usize,
+    // None if the ref was created by this entry
+    pub old: Option<String>,
+    pub new: String,
+    pub message: String,
+    pub committer: String,
+    // seconds since the unix epoch
+    pub time: i64,
+}
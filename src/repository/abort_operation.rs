@@ -0,0 +1,191 @@
+use super::filters::filtered_checkout;
+use crate::{repository_state::RepositoryState, Repository, Result};
+use std::fs;
+
+// a rebase is reset like `git rebase --abort`, other operations like
+// `git reset --merge`, keeping changes to paths the operation did not touch
+pub fn abort_operation(repository: &Repository) -> Result<RepositoryState> {
+    let state: RepositoryState = repository.repo.state().into();
+
+    match state {
+        RepositoryState::Clean => return Ok(state),
+        // rebase moves HEAD, so return to the branch tip it started from
+        RepositoryState::Rebase => match repository.repo.open_rebase(None) {
+            Ok(mut rebase) => {
+                filtered_checkout(repository, || Ok(rebase.abort()?))?;
+                log::debug!("aborted rebase");
+                return Ok(state);
+            }
+            Err(_) => {
+                let commit = repository.repo.revparse_single("ORIG_HEAD")?.peel_to_commit()?;
+
+                filtered_checkout(repository, || {
+                    Ok(repository
+                        .repo
+                        .reset(commit.as_object(), git2::ResetType::Hard, None)?)
+                })?;
+
+                repository.repo.cleanup_state()?;
+
+                log::debug!("aborted {:?} at `{}`", state, commit.id());
+
+                return Ok(state);
+            }
+        },
+        // merge, cherry-pick and revert leave HEAD where it was
+        _ => {}
+    }
+
+    let commit = repository.find_last_commit()?;
+
+    let tree = commit.tree()?;
+
+    // the operation staged its results, local edits were left unstaged
+    let index = repository.repo.index()?;
+
+    let diff = repository.repo.diff_tree_to_index(Some(&tree), Some(&index), None)?;
+
+    let mut paths: Vec<String> = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+
+    if !paths.is_empty() {
+        repository.repo.reset_default(Some(commit.as_object()), &paths)?;
+
+        let workdir = repository
+            .repo
+            .workdir()
+            .ok_or_else(|| crate::Error::from_message("repository has no working tree"))?;
+
+        // paths the operation added are untracked now, checkout would keep them
+        for path in &paths {
+            if tree.get_path(std::path::Path::new(path)).is_err() && workdir.join(path).is_file() {
+                fs::remove_file(workdir.join(path))?;
+            }
+        }
+
+        let mut checkout = git2::build::CheckoutBuilder::new();
+
+        checkout.force().disable_pathspec_match(true);
+
+        for path in &paths {
+            checkout.path(path);
+        }
+
+        filtered_checkout(repository, || {
+            Ok(repository.repo.checkout_tree(tree.as_object(), Some(&mut checkout))?)
+        })?;
+    }
+
+    repository.repo.cleanup_state()?;
+
+    log::debug!("aborted {:?} at `{}`", state, commit.id());
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test_support::diverge;
+    use crate::{Repository, RepositoryState, Result};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn abort_merge_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        let main = diverge(
+            &repository,
+            &[("foo.txt", "base\n"), ("bar.txt", "base\n")],
+            &[("foo.txt", "main\n"), ("baz.txt", "main\n")],
+            &[("foo.txt", "dev\n")],
+        )?;
+
+        let dev = repository.find_last_commit()?.id();
+
+        // an uncommitted edit the merge does not touch
+        File::create(temp_path.join("bar.txt"))?.write_all(b"local\n")?;
+
+        let main = repository.repo.find_annotated_commit(main)?;
+
+        assert!(repository.merge(main, "Merge branch 'main'")?.is_none());
+
+        assert!(repository.abort_operation()? == RepositoryState::Merge);
+
+        assert!(read_to_string(temp_path.join("bar.txt"))? == "local\n");
+
+        // added by the merge
+        assert!(!temp_path.join("baz.txt").exists());
+
+        assert!(repository.repo.state() == git2::RepositoryState::Clean);
+
+        assert!(repository.conflicts()?.is_empty());
+
+        assert!(repository.find_last_commit()?.id() == dev);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "dev\n");
+
+        // nothing left to abort
+        assert!(repository.abort_operation()? == RepositoryState::Clean);
+
+        Ok(())
+    }
+
+    #[test]
+    fn abort_rebase_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        let main = diverge(
+            &repository,
+            &[("foo.txt", "base\n"), ("bar.txt", "base\n")],
+            &[("foo.txt", "main\n"), ("baz.txt", "main\n")],
+            &[("foo.txt", "dev\n")],
+        )?;
+
+        let dev = repository.find_last_commit()?.id();
+
+        let main = repository.repo.find_annotated_commit(main)?;
+
+        assert!(repository.rebase(main)?.is_none());
+
+        assert!(repository.abort_operation()? == RepositoryState::Rebase);
+
+        assert!(repository.repo.state() == git2::RepositoryState::Clean);
+
+        assert!(repository.head_branch()?.name()? == Some("dev"));
+
+        assert!(repository.find_last_commit()?.id() == dev);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "dev\n");
+
+        Ok(())
+    }
+}
@@ -1,35 +1,86 @@
+use super::filters::{stage_filtered, write_index};
 use crate::{Repository, Result};
 use std::path::Path;
 
-pub fn add(repository: &Repository) -> Result<(git2::Oid, String)> {
+// stages the given paths, or every path if none are given, including
+// deleted files like `git add -A`
+pub fn add(repository: &Repository, paths: &[&str]) -> Result<(git2::Oid, String)> {
     let mut index = repository.repo.index()?;
 
-    let mut message = "".to_owned();
+    let mut added = vec![];
 
-    let cb = &mut |path: &Path, _matched_spec: &[u8]| -> i32 {
-        let status = repository.repo.status_file(path).unwrap();
+    let mut filtered = vec![];
+
+    let mut error = None;
+
+    let mut matched = |path: &Path| -> Result<i32> {
+        let status = repository.repo.status_file(path)?;
 
-        let ret = if status.contains(git2::Status::WT_MODIFIED)
-            || status.contains(git2::Status::WT_NEW)
+        if !status.intersects(
+            git2::Status::WT_MODIFIED | git2::Status::WT_NEW | git2::Status::WT_DELETED,
+        ) {
+            return Ok(1);
+        }
+
+        let name = path.to_string_lossy().into_owned();
+
+        // filtered files are cleaned before anything is stored, libgit2
+        // would store the working tree contents
+        if !status.contains(git2::Status::WT_DELETED)
+            && repository.filters.find(repository, &name)?.is_some()
         {
-            message = if message == "" {
-                format!("{}", path.display())
-            } else {
-                format!("{}, {}", message, path.display())
-            };
-            0
-        } else {
-            1
-        };
-
-        ret
+            filtered.push(name);
+            return Ok(1);
+        }
+
+        added.push(name);
+
+        Ok(0)
+    };
+
+    let cb = &mut |path: &Path, _matched_spec: &[u8]| -> i32 {
+        match matched(path) {
+            Ok(ret) => ret,
+            Err(err) => {
+                error = Some(err);
+                -1
+            }
+        }
     };
 
     let cb = Some(cb as &mut git2::IndexMatchedPath);
 
-    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, cb)?;
+    // paths are file names, not globs
+    let flags = match paths.is_empty() {
+        true => git2::IndexAddOption::DEFAULT,
+        false => git2::IndexAddOption::DISABLE_PATHSPEC_MATCH,
+    };
+
+    let pathspecs = match paths.is_empty() {
+        true => vec!["*"],
+        false => paths.to_vec(),
+    };
+
+    let result = index.add_all(pathspecs.iter(), flags, cb);
+
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    result?;
+
+    for path in &filtered {
+        // a smudged file differs from what is staged without being modified
+        if stage_filtered(repository, &mut index, path)? {
+            added.push(path.clone());
+        }
+    }
+
+    write_index(&mut index, &filtered)?;
+
+    added.sort();
 
-    index.write()?;
+    let message = added.join(", ");
 
     let oid = index.write_tree()?;
 
@@ -58,7 +109,7 @@ mod test {
 
         file.write_all(b"Hello, world!")?;
 
-        let (oid, message) = repository.add()?;
+        let (oid, message) = repository.add(&[])?;
 
         assert!(message == "foo.txt");
 
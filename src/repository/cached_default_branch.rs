@@ -0,0 +1,34 @@
+use crate::{settings::Settings, Repository};
+
+// reads the remote HEAD recorded by clone or refresh_remote_info
+// instead of connecting to the remote
+pub fn cached_default_branch<'a>(
+    repository: &'a Repository,
+    settings: &Settings,
+) -> (Option<String>, Option<git2::Remote<'a>>) {
+    if let Some(name) = &settings.default_branch {
+        return (Some(name.to_owned()), None);
+    }
+
+    let remote = match repository.default_remote(settings) {
+        Ok(remote) => remote,
+        Err(_) => return (None, None),
+    };
+
+    let prefix = match remote.name() {
+        Some(name) => format!("refs/remotes/{}/", name),
+        None => return (None, Some(remote)),
+    };
+
+    let default_branch = repository
+        .repo
+        .find_reference(&format!("{}HEAD", prefix))
+        .ok()
+        .and_then(|head| {
+            head.symbolic_target()
+                .and_then(|target| target.strip_prefix(&prefix))
+                .map(|name| name.to_owned())
+        });
+
+    (default_branch, Some(remote))
+}
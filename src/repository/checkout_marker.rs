@@ -0,0 +1,49 @@
+use crate::{Repository, Result};
+use std::fs;
+
+// written next to HEAD while git2kit checks out a tree and moves a ref,
+// so an interrupted checkout can be finished by recover
+pub const CHECKOUT_MARKER: &str = "GIT2KIT_CHECKOUT";
+
+pub fn begin_checkout(repository: &Repository, reference: &str, oid: git2::Oid) -> Result<()> {
+    fs::write(
+        repository.repo.path().join(CHECKOUT_MARKER),
+        format!("{} {}\n", reference, oid),
+    )?;
+
+    Ok(())
+}
+
+pub fn end_checkout(repository: &Repository) -> Result<()> {
+    match fs::remove_file(repository.repo.path().join(CHECKOUT_MARKER)) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// a checkout refused over conflicts left the tree as it was, so there is
+// nothing for recover to finish; any other failure may have written part
+// of the tree and keeps the marker
+pub fn abandon_checkout(repository: &Repository, err: git2::Error) -> crate::Error {
+    if err.code() == git2::ErrorCode::Conflict {
+        if let Err(end_err) = end_checkout(repository) {
+            log::warn!("checkout: failed to remove the checkout marker: {}", end_err);
+        }
+    }
+
+    err.into()
+}
+
+pub fn read_checkout(repository: &Repository) -> Result<Option<(String, git2::Oid)>> {
+    let contents = match fs::read_to_string(repository.repo.path().join(CHECKOUT_MARKER)) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    match contents.trim().split_once(' ') {
+        Some((reference, oid)) => Ok(Some((reference.to_owned(), git2::Oid::from_str(oid)?))),
+        None => Err(crate::Error::from_message("checkout marker is malformed")),
+    }
+}
@@ -0,0 +1,22 @@
+use super::filters::filtered_checkout;
+use super::head_status::REFS_TAGS_NAMESPACE;
+use crate::{Repository, Result};
+
+// detaches HEAD at the tagged commit so new commits do not move the tag
+pub fn checkout_tag(repository: &Repository, name: &str) -> Result<()> {
+    let commit = repository
+        .repo
+        .find_reference(&format!("{}{}", REFS_TAGS_NAMESPACE, name))?
+        .peel_to_commit()?;
+
+    filtered_checkout(repository, || {
+        repository.repo.checkout_tree(
+            commit.as_object(),
+            Some(git2::build::CheckoutBuilder::new().safe()),
+        )?;
+
+        Ok(repository.repo.set_head_detached(commit.id())?)
+    })?;
+
+    Ok(())
+}
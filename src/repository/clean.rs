@@ -0,0 +1,112 @@
+use crate::{Repository, Result};
+use std::fs;
+
+// removes untracked files that are not ignored, returning their paths,
+// with dry_run only lists what would be removed
+pub fn clean(repository: &Repository, dry_run: bool) -> Result<Vec<String>> {
+    let statuses = repository.repo.statuses(Some(
+        git2::StatusOptions::new()
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false)
+            .exclude_submodules(true),
+    ))?;
+
+    let workdir = repository
+        .repo
+        .workdir()
+        .ok_or_else(|| crate::Error::from_message("repository has no working tree"))?;
+
+    let mut paths = vec![];
+
+    for entry in statuses.iter() {
+        if !entry.status().contains(git2::Status::WT_NEW) {
+            continue;
+        }
+
+        match entry.path() {
+            Some(path) => paths.push(path.to_owned()),
+            None => return Err(crate::Error::from_message("path is invalid utf-8")),
+        }
+    }
+
+    if dry_run {
+        return Ok(paths);
+    }
+
+    for path in &paths {
+        let file_path = workdir.join(path);
+
+        fs::remove_file(&file_path)?;
+
+        // remove directories left empty, stopping at the working tree
+        let mut dir = file_path.parent();
+        while let Some(parent) = dir {
+            if parent == workdir || fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+
+        log::debug!("clean: removed `{}`", path);
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result};
+    use std::fs::{create_dir_all, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn clean_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"tracked\n")?;
+
+        File::create(temp_path.join(".gitignore"))?.write_all(b"*.log\n")?;
+
+        repository.commit()?;
+
+        create_dir_all(temp_path.join("dir/nested"))?;
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"untracked\n")?;
+
+        File::create(temp_path.join("dir/nested/baz.txt"))?.write_all(b"untracked\n")?;
+
+        File::create(temp_path.join("debug.log"))?.write_all(b"ignored\n")?;
+
+        let mut paths = repository.clean(true)?;
+
+        paths.sort();
+
+        assert!(paths == vec!["bar.txt".to_string(), "dir/nested/baz.txt".to_string()]);
+
+        // a dry run removes nothing
+        assert!(temp_path.join("bar.txt").exists());
+
+        repository.clean(false)?;
+
+        assert!(!temp_path.join("bar.txt").exists());
+
+        assert!(!temp_path.join("dir").exists());
+
+        assert!(temp_path.join("foo.txt").exists());
+
+        assert!(temp_path.join("debug.log").exists());
+
+        assert!(repository.clean(true)?.is_empty());
+
+        Ok(())
+    }
+}
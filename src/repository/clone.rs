@@ -1,10 +1,24 @@
+use super::fetch::git_depth;
+use super::lfs::enable_lfs;
 use super::Repository;
-use crate::{Origin, Result};
+use crate::{LfsStore, Origin, Result, Settings};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct CloneOptions {
+    // limits the history to that many commits, None clones all of it
+    pub depth: Option<u32>,
+    // configures the clone, its encryption keys decrypt the checked out files
+    pub settings: Settings,
+    // enables LFS on the clone, downloading the checked out objects
+    pub lfs_store: Option<Arc<dyn LfsStore>>,
+}
 
 pub fn clone(
     dataset_dir: PathBuf,
     remote: &Origin,
+    options: &CloneOptions,
 ) -> Result<Repository> {
     // clone to dataset_dir from remote_url with remote_token
     // let repo = match Repository::clone(remote.url, dataset_dir) {
@@ -22,6 +36,10 @@ pub fn clone(
     let mut fo = git2::FetchOptions::new();
     fo.remote_callbacks(callbacks);
 
+    if let Some(depth) = options.depth {
+        fo.depth(git_depth(depth));
+    }
+
     // Prepare builder.
     let mut builder = git2::build::RepoBuilder::new();
 
@@ -42,7 +60,21 @@ pub fn clone(
 
     // set config.remote.origin.token
 
-    Ok(Repository {repo})
+    let mut repository = Repository {
+        repo,
+        merge_drivers: Default::default(),
+        filters: Default::default(),
+        lfs_store: None,
+        settings: Default::default(),
+    };
+
+    if let Some(store) = &options.lfs_store {
+        enable_lfs(&mut repository, store.clone())?;
+    }
+
+    repository.configure(options.settings.clone())?;
+
+    Ok(repository)
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use super::commit_to_branch::build_tree;
 use crate::{Repository, Result};
 use std::path::Path;
 
@@ -6,11 +7,20 @@ use std::path::Path;
 // if no new files and last commit, don't commit
 // if new files and last commit, commit message
 pub fn commit(repository: &Repository) -> Result<git2::Oid> {
-    let (oid, message) = repository.add()?;
+    commit_paths(repository, &[])
+}
+
+// commits only the given paths, other changes stay in the working tree
+// and changes staged before are left staged but uncommitted
+pub fn commit_paths(repository: &Repository, paths: &[&str]) -> Result<git2::Oid> {
+    let (oid, message) = repository.add(paths)?;
 
     let signature = git2::Signature::now("name", "name@mail.com")?;
 
-    let tree = repository.repo.find_tree(oid)?;
+    let tree = match paths.is_empty() {
+        true => repository.repo.find_tree(oid)?,
+        false => repository.repo.find_tree(paths_tree(repository, paths)?)?,
+    };
 
     let oid_new = match repository.find_last_commit() {
         Ok(c) => {
@@ -55,6 +65,31 @@ pub fn commit(repository: &Repository) -> Result<git2::Oid> {
     Ok(oid_new)
 }
 
+// the HEAD tree with the staged entries of the given paths
+fn paths_tree(repository: &Repository, paths: &[&str]) -> Result<git2::Oid> {
+    let index = repository.repo.index()?;
+
+    let base_tree = match repository.find_last_commit() {
+        Ok(commit) => Some(commit.tree()?),
+        Err(_) => None,
+    };
+
+    let mut changes = vec![];
+
+    for path in paths {
+        let blob = index
+            .get_path(Path::new(path), 0)
+            .map(|entry| (entry.id, entry.mode as i32));
+
+        changes.push((path.split('/').collect::<Vec<&str>>(), blob));
+    }
+
+    match build_tree(repository, base_tree.as_ref(), &changes)? {
+        Some(oid) => Ok(oid),
+        None => Ok(repository.repo.treebuilder(None)?.write()?),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Repository, Result};
@@ -104,4 +139,34 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn commit_deleted_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"Hello, world!")?;
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"Hello, world!")?;
+
+        repository.commit()?;
+
+        std::fs::remove_file(temp_path.join("foo.txt"))?;
+
+        // deleted files are committed like `git add -A`
+        let oid = repository.commit()?;
+
+        let commit = repository.repo.find_commit(oid)?;
+
+        assert!(commit.message() == Some("foo.txt"));
+
+        assert!(commit.tree()?.get_path(std::path::Path::new("foo.txt")).is_err());
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        Ok(())
+    }
 }
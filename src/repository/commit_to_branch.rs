@@ -0,0 +1,296 @@
+use super::filters::clean;
+use super::head_status::REFS_HEADS_NAMESPACE;
+use crate::{Repository, Result};
+use std::collections::BTreeMap;
+
+// describes a commit built from contents in memory instead of the working tree
+#[derive(Debug, Clone)]
+pub struct CommitBuilder {
+    branch: String,
+    message: String,
+    parent: Option<String>,
+    // None deletes the path
+    changes: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl CommitBuilder {
+    pub fn new(branch: &str, message: &str) -> Self {
+        CommitBuilder {
+            branch: branch.to_string(),
+            message: message.to_string(),
+            parent: None,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    // defaults to the tip of the branch, the commit fails if the branch
+    // no longer points at the parent when it is written
+    pub fn parent(&mut self, revision: &str) -> &mut Self {
+        self.parent = Some(revision.to_string());
+        self
+    }
+
+    pub fn write(&mut self, path: &str, content: impl Into<Vec<u8>>) -> &mut Self {
+        self.changes.insert(path.to_string(), Some(content.into()));
+        self
+    }
+
+    pub fn delete(&mut self, path: &str) -> &mut Self {
+        self.changes.insert(path.to_string(), None);
+        self
+    }
+}
+
+// does not touch the index or working tree, so it works on bare repositories
+pub fn commit_to_branch(repository: &Repository, builder: &CommitBuilder) -> Result<git2::Oid> {
+    let reference_name = format!("{}{}", REFS_HEADS_NAMESPACE, builder.branch);
+
+    let tip = match repository.repo.find_reference(&reference_name) {
+        Ok(reference) => Some(reference.peel_to_commit()?.id()),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let parent = match &builder.parent {
+        Some(revision) => Some(repository.repo.revparse_single(revision)?.peel_to_commit()?),
+        None => match tip {
+            Some(oid) => Some(repository.repo.find_commit(oid)?),
+            None => None,
+        },
+    };
+
+    let base_tree = match &parent {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+
+    let mut changes = vec![];
+
+    for path in builder.changes.keys() {
+        validate_path(path)?;
+    }
+
+    for (path, content) in &builder.changes {
+        let blob = match content {
+            Some(content) => Some((
+                repository.repo.blob(&clean(repository, path, content)?)?,
+                git2::FileMode::Blob.into(),
+            )),
+            None => None,
+        };
+
+        changes.push((path.split('/').collect::<Vec<&str>>(), blob));
+    }
+
+    let tree_oid = match build_tree(repository, base_tree.as_ref(), &changes)? {
+        Some(oid) => oid,
+        None => repository.repo.treebuilder(None)?.write()?,
+    };
+
+    let tree = repository.repo.find_tree(tree_oid)?;
+
+    let signature = repository.signature()?;
+
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    // write the commit without moving any ref yet
+    let oid = repository.repo.commit(
+        None,
+        &signature,
+        &signature,
+        &builder.message,
+        &tree,
+        &parents,
+    )?;
+
+    let log_message = format!("git2kit: commit to {}", builder.branch);
+
+    let expected = parent.as_ref().map(|parent| parent.id());
+
+    match (tip, expected) {
+        // compare and swap so a concurrent writer is not overwritten
+        (Some(_), Some(expected)) => {
+            repository
+                .repo
+                .reference_matching(&reference_name, oid, true, expected, &log_message)
+                .map_err(|err| match err.code() {
+                    git2::ErrorCode::Modified => crate::Error::from_message(format!(
+                        "branch `{}` moved since `{}`",
+                        builder.branch, expected
+                    )),
+                    _ => err.into(),
+                })?;
+        }
+        (Some(_), None) => {
+            return Err(crate::Error::from_message(format!(
+                "branch `{}` already exists",
+                builder.branch
+            )))
+        }
+        (None, _) => {
+            repository
+                .repo
+                .reference(&reference_name, oid, false, &log_message)?;
+        }
+    }
+
+    log::debug!("committed `{}` to `{}`", oid, builder.branch);
+
+    Ok(oid)
+}
+
+// paths are relative to the root of the repository and may not leave it
+pub fn validate_path(path: &str) -> Result<()> {
+    let valid = !path.is_empty()
+        && !std::path::Path::new(path).is_absolute()
+        && path
+            .split(['/', '\\'])
+            .all(|component| !matches!(component, "" | "." | ".." | ".git"));
+
+    match valid {
+        true => Ok(()),
+        false => Err(crate::Error::from_message(format!("invalid path `{}`", path))),
+    }
+}
+
+// changes are path components with the blob and file mode to write, or
+// None to delete; returns None if the resulting tree is empty
+pub fn build_tree(
+    repository: &Repository,
+    base: Option<&git2::Tree>,
+    changes: &[(Vec<&str>, Option<(git2::Oid, i32)>)],
+) -> Result<Option<git2::Oid>> {
+    let mut builder = repository.repo.treebuilder(base)?;
+
+    let mut subtrees: BTreeMap<&str, Vec<(Vec<&str>, Option<(git2::Oid, i32)>)>> =
+        BTreeMap::new();
+
+    for (components, blob) in changes {
+        match components.as_slice() {
+            [name] => match blob {
+                Some((blob, mode)) => {
+                    builder.insert(name, *blob, *mode)?;
+                }
+                None => {
+                    if builder.get(name)?.is_some() {
+                        builder.remove(name)?;
+                    }
+                }
+            },
+            [name, rest @ ..] => subtrees
+                .entry(name)
+                .or_default()
+                .push((rest.to_vec(), *blob)),
+            [] => (),
+        }
+    }
+
+    for (name, changes) in subtrees {
+        let subtree = match builder.get(name)? {
+            Some(entry) if entry.kind() == Some(git2::ObjectType::Tree) => {
+                Some(repository.repo.find_tree(entry.id())?)
+            }
+            _ => None,
+        };
+
+        match build_tree(repository, subtree.as_ref(), &changes)? {
+            Some(oid) => {
+                builder.insert(name, oid, git2::FileMode::Tree.into())?;
+            }
+            // git does not store empty directories
+            None => {
+                if builder.get(name)?.is_some() {
+                    builder.remove(name)?;
+                }
+            }
+        }
+    }
+
+    match builder.len() {
+        0 => Ok(None),
+        _ => Ok(Some(builder.write()?)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CommitBuilder;
+    use crate::{Repository, Result};
+    use temp_dir::TempDir;
+
+    #[test]
+    fn commit_to_branch_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init_bare(&temp_path)?;
+
+        let first = repository.commit_to_branch(
+            CommitBuilder::new("main", "first")
+                .write("foo.txt", "first\n")
+                .write("dir/bar.csv", "a,b\n")
+                .write("dir/nested/baz.csv", "c,d\n"),
+        )?;
+
+        assert!(repository.read_blob("main", "dir/nested/baz.csv")? == b"c,d\n");
+
+        let second = repository.commit_to_branch(
+            CommitBuilder::new("main", "second")
+                .write("foo.txt", "second\n")
+                .delete("dir/bar.csv")
+                .delete("dir/nested/baz.csv"),
+        )?;
+
+        let commit = repository.repo.find_commit(second)?;
+
+        assert!(commit.parent_id(0)? == first);
+
+        assert!(commit.message() == Some("second"));
+
+        // emptied directories are removed
+        let paths: Vec<String> = repository
+            .list_tree("main", None, true)?
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect();
+
+        assert!(paths == vec!["foo.txt".to_string()]);
+
+        assert!(repository.read_blob("main", "foo.txt")? == b"second\n");
+
+        // the branch moved past the parent, so the commit is refused
+        let stale = repository.commit_to_branch(
+            CommitBuilder::new("main", "stale")
+                .parent(&first.to_string())
+                .write("foo.txt", "stale\n"),
+        );
+
+        assert!(stale.is_err());
+
+        assert!(repository.resolve_revision("main")? == second);
+
+        // a new branch can start from any revision
+        let third = repository.commit_to_branch(
+            CommitBuilder::new("dev", "third")
+                .parent(&first.to_string())
+                .write("qux.txt", "third\n"),
+        )?;
+
+        assert!(repository.repo.find_commit(third)?.parent_id(0)? == first);
+
+        assert!(repository.read_blob("dev", "dir/bar.csv")? == b"a,b\n");
+
+        // paths may not leave the repository
+        for path in ["../escape.txt", "/etc/passwd", "dir/../../escape.txt", ".git/config"] {
+            assert!(repository.commit_to_branch(CommitBuilder::new("dev", "bad").write(path, "x")).is_err());
+        }
+
+        assert!(repository.resolve_revision("dev")? == third);
+
+        Ok(())
+    }
+}
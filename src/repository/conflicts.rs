@@ -0,0 +1,27 @@
+use crate::{Repository, Result};
+use bstr::ByteSlice;
+
+pub fn conflicts(repository: &Repository) -> Result<Vec<String>> {
+    let mut index = repository.repo.index()?;
+
+    // pick up conflicts written through another handle, such as by stash
+    index.read(false)?;
+
+    let mut paths = vec![];
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+
+        // a side is missing if the file was added or deleted there
+        let entry = conflict
+            .our
+            .or(conflict.their)
+            .or(conflict.ancestor);
+
+        if let Some(entry) = entry {
+            paths.push(entry.path.as_bstr().to_string());
+        }
+    }
+
+    Ok(paths)
+}
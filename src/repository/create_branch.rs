@@ -1,10 +1,13 @@
 use super::{Repository, Result, Settings};
+use crate::stash::StashOutcome;
 
+// with autostash, local changes are carried over to the new branch
 pub fn create_branch(
     repository: &Repository,
     settings: &Settings,
     name: &str,
-) -> crate::Result<()> {
+    autostash: bool,
+) -> crate::Result<Option<StashOutcome>> {
     let commit = match &settings.default_branch {
         Some(default_branch) => repository
             .repo
@@ -16,15 +19,76 @@ pub fn create_branch(
 
     let working_tree_status = repository.working_tree_status()?;
 
-    if working_tree_status.is_dirty() {
+    if working_tree_status.is_dirty() && !autostash {
         return Err(crate::Error::from_message(
             "working tree has uncommitted changes",
         ));
     }
 
+    let stashed = match autostash {
+        true => repository.stash_save(Some("git2kit: autostash"), true)?,
+        false => None,
+    };
+
     let branch = repository.repo.branch(name, &commit, false)?;
 
-    repository.switch(&branch.into_reference())?;
+    if let Err(err) = repository.switch(&branch.into_reference()) {
+        // put the changes back where they were
+        if stashed.is_some() {
+            if let Err(pop_err) = repository.stash_pop(0) {
+                log::warn!("create_branch: failed to restore autostash: {}", pop_err);
+            }
+        }
+        return Err(err);
+    }
+
+    match stashed {
+        Some(_) => Ok(Some(repository.stash_pop(0)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result, Settings, StashOutcome};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn create_branch_autostash_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
 
-    Ok(())
+        repository.commit()?;
+
+        // a clean tree needs nothing carried over
+        assert!(repository.create_branch(&Settings::default(), "dev", false)?.is_none());
+
+        assert!(repository.head_branch()?.name()? == Some("dev"));
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"local\n")?;
+
+        assert!(repository.create_branch(&Settings::default(), "refused", false).is_err());
+
+        let outcome = repository.create_branch(&Settings::default(), "feature", true)?;
+
+        assert!(outcome == Some(StashOutcome::Applied));
+
+        assert!(repository.head_branch()?.name()? == Some("feature"));
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "local\n");
+
+        assert!(repository.stashes()?.is_empty());
+
+        Ok(())
+    }
 }
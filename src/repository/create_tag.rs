@@ -0,0 +1,28 @@
+use crate::{Repository, Result};
+
+// annotated if a message is given, lightweight otherwise
+pub fn create_tag(
+    repository: &Repository,
+    name: &str,
+    revision: &str,
+    message: Option<&str>,
+) -> Result<git2::Oid> {
+    let commit = repository.repo.revparse_single(revision)?.peel_to_commit()?;
+
+    let oid = match message {
+        Some(message) => {
+            let signature = repository.signature()?;
+
+            repository
+                .repo
+                .tag(name, commit.as_object(), &signature, message, false)?
+        }
+        None => repository
+            .repo
+            .tag_lightweight(name, commit.as_object(), false)?,
+    };
+
+    log::debug!("created tag `{}` at `{}`", name, commit.id());
+
+    Ok(oid)
+}
@@ -3,13 +3,17 @@ use crate::{repository_status::RepositoryStatus, Repository, Result};
 pub fn create_unborn(
     repository: &Repository,
     status: &RepositoryStatus,
+    branch_name: &str,
+    remote_name: &str,
     fetch_commit: git2::AnnotatedCommit,
 ) -> Result<()> {
     debug_assert!(status.head.is_unborn());
 
     let commit = repository.repo.find_commit(fetch_commit.id())?;
 
-    let branch = repository.repo.branch(&status.head.name, &commit, false)?;
+    let mut branch = repository.repo.branch(branch_name, &commit, false)?;
+
+    branch.set_upstream(Some(&format!("{}/{}", remote_name, branch_name)))?;
 
     repository.switch(&branch.into_reference())?;
 
@@ -0,0 +1,228 @@
+use super::csv_table::CsvTable;
+use crate::csv_diff::{CsvDiff, CsvRow, CsvRowChange};
+use crate::file_change::FileChangeKind;
+use crate::{Repository, Result};
+
+pub struct CsvDiffOptions {
+    // column identifying a row, None uses the first column
+    pub key: Option<String>,
+    pub delimiter: u8,
+}
+
+impl Default for CsvDiffOptions {
+    fn default() -> Self {
+        CsvDiffOptions {
+            key: None,
+            delimiter: b',',
+        }
+    }
+}
+
+// row level changes of every csv file changed between the two revisions,
+// a file that cannot be parsed is reported with an error instead
+pub fn csv_diff(
+    repository: &Repository,
+    from: &str,
+    to: &str,
+    options: &CsvDiffOptions,
+) -> Result<Vec<CsvDiff>> {
+    let mut diffs = vec![];
+
+    for change in repository.diff(from, to)? {
+        if !change.path.ends_with(".csv") {
+            continue;
+        }
+
+        let old_path = change.old_path.as_deref().unwrap_or(&change.path);
+
+        let old = match change.kind {
+            FileChangeKind::Added => None,
+            _ => Some(repository.read_blob(from, old_path)?),
+        };
+
+        let new = match change.kind {
+            FileChangeKind::Deleted => None,
+            _ => Some(repository.read_blob(to, &change.path)?),
+        };
+
+        let diff = match diff_tables(change.path.clone(), old.as_deref(), new.as_deref(), options) {
+            Ok(diff) => diff,
+            Err(err) => {
+                log::debug!("csv_diff: cannot compare `{}`: {}", change.path, err);
+
+                CsvDiff {
+                    path: change.path,
+                    added: vec![],
+                    removed: vec![],
+                    changed: vec![],
+                    error: Some(err.to_string()),
+                }
+            }
+        };
+
+        if !diff.is_empty() {
+            diffs.push(diff);
+        }
+    }
+
+    Ok(diffs)
+}
+
+fn diff_tables(
+    path: String,
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    options: &CsvDiffOptions,
+) -> Result<CsvDiff> {
+    let parse = |content: Option<&[u8]>| match content {
+        Some(content) => CsvTable::parse(content, options.key.as_deref(), options.delimiter),
+        None => Ok(CsvTable::empty(vec![])),
+    };
+
+    let old = parse(old)?;
+
+    let new = parse(new)?;
+
+    let mut diff = CsvDiff {
+        path,
+        added: vec![],
+        removed: vec![],
+        changed: vec![],
+        error: None,
+    };
+
+    for (key, values) in &new.rows {
+        match old.get(key) {
+            None => diff.added.push(CsvRow {
+                key: key.clone(),
+                values: new.named(values),
+            }),
+            Some(old_values) => {
+                let (old_named, new_named) = (old.named(old_values), new.named(values));
+
+                if old_named != new_named {
+                    diff.changed.push(CsvRowChange {
+                        key: key.clone(),
+                        old: old_named,
+                        new: new_named,
+                    });
+                }
+            }
+        }
+    }
+
+    for (key, values) in &old.rows {
+        if new.get(key).is_none() {
+            diff.removed.push(CsvRow {
+                key: key.clone(),
+                values: old.named(values),
+            });
+        }
+    }
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod test {
+    use super::CsvDiffOptions;
+    use crate::{Repository, Result};
+    use std::fs;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn csv_diff_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(
+            temp_path.join("people.csv"),
+            "name,id,city\nada,1,london\nalan,2,wilmslow\ngrace,3,new york\n",
+        )?;
+
+        fs::write(temp_path.join("notes.txt"), "not a dataset")?;
+
+        let first = repository.commit()?;
+
+        // rows reordered, one changed, one removed, one added
+        fs::write(
+            temp_path.join("people.csv"),
+            "name,id,city\ngrace,3,arlington\nada,1,london\nedsger,4,rotterdam\n",
+        )?;
+
+        fs::write(temp_path.join("notes.txt"), "changed")?;
+
+        let second = repository.commit()?;
+
+        let options = CsvDiffOptions {
+            key: Some("id".to_string()),
+            ..Default::default()
+        };
+
+        let diffs = repository.csv_diff(&first.to_string(), &second.to_string(), &options)?;
+
+        assert!(diffs.len() == 1);
+
+        let diff = &diffs[0];
+
+        assert!(diff.path == "people.csv");
+
+        assert!(diff.added.len() == 1 && diff.added[0].key == "4");
+
+        assert!(diff.added[0].values["name"] == "edsger");
+
+        assert!(diff.removed.len() == 1 && diff.removed[0].key == "2");
+
+        assert!(diff.changed.len() == 1 && diff.changed[0].key == "3");
+
+        assert!(diff.changed[0].old["city"] == "new york");
+
+        assert!(diff.changed[0].new["city"] == "arlington");
+
+        assert!(diff.error.is_none());
+
+        // an unknown key column is reported for the file
+        let options = CsvDiffOptions {
+            key: Some("missing".to_string()),
+            ..Default::default()
+        };
+
+        let diffs = repository.csv_diff(&first.to_string(), &second.to_string(), &options)?;
+
+        assert!(diffs.len() == 1 && diffs[0].error == Some("no key column `missing`".to_string()));
+
+        // a duplicate key in one file does not hide the changes to another
+        fs::write(temp_path.join("people.csv"), "name,id\nada,1\nalan,1\n")?;
+
+        fs::write(temp_path.join("cities.csv"), "id,name\n1,london\n")?;
+
+        let third = repository.commit()?;
+
+        let options = CsvDiffOptions {
+            key: Some("id".to_string()),
+            ..Default::default()
+        };
+
+        let diffs = repository.csv_diff(&second.to_string(), &third.to_string(), &options)?;
+
+        assert!(diffs.len() == 2);
+
+        let cities = diffs.iter().find(|diff| diff.path == "cities.csv").unwrap();
+
+        assert!(cities.error.is_none() && cities.added.len() == 1);
+
+        let people = diffs.iter().find(|diff| diff.path == "people.csv").unwrap();
+
+        assert!(people.error == Some("duplicate key `1`".to_string()));
+
+        assert!(people.added.is_empty() && people.removed.is_empty() && people.changed.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use super::csv_table::CsvTable;
+use crate::Result;
+use crate::merge_driver::{MergeDriver, MergeOutcome};
+use std::collections::HashSet;
+
+// merges csv rows by key, registered for `*.csv` and `merge=csv` by default
+pub struct CsvMergeDriver {
+    // column identifying a row, None uses the first column
+    pub key: Option<String>,
+    pub delimiter: u8,
+}
+
+impl Default for CsvMergeDriver {
+    fn default() -> Self {
+        CsvMergeDriver {
+            key: None,
+            delimiter: b',',
+        }
+    }
+}
+
+impl MergeDriver for CsvMergeDriver {
+    fn merge(
+        &self,
+        _path: &str,
+        base: Option<&[u8]>,
+        ours: &[u8],
+        theirs: &[u8],
+    ) -> Result<MergeOutcome> {
+        merge_csv(base, ours, theirs, self.key.as_deref(), self.delimiter)
+    }
+}
+
+// three-way merge of csv rows by key, ours keeps its order and rows
+// only theirs added are appended in their order, conflicts are keys
+// changed differently on both sides or nothing if the headers differ or
+// a side cannot be parsed, rows are copied as written on the side they
+// are taken from
+pub fn merge_csv(
+    base: Option<&[u8]>,
+    ours: &[u8],
+    theirs: &[u8],
+    key: Option<&str>,
+    delimiter: u8,
+) -> Result<MergeOutcome> {
+    let parse = |content: &[u8]| CsvTable::parse(content, key, delimiter);
+
+    let tables = (parse(ours), parse(theirs), base.map(parse).transpose());
+
+    let (ours, theirs, base) = match tables {
+        (Ok(ours), Ok(theirs), Ok(base)) => (ours, theirs, base),
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            log::debug!("merge_csv: falling back to a textual conflict: {}", err);
+            return Ok(MergeOutcome::Conflicted(vec![]));
+        }
+    };
+
+    let base = base.unwrap_or_else(|| CsvTable::empty(ours.headers.clone()));
+
+    if ours.headers != theirs.headers || base.headers != ours.headers {
+        return Ok(MergeOutcome::Conflicted(vec![]));
+    }
+
+    let mut seen = HashSet::new();
+
+    let keys: Vec<&String> = ours
+        .rows
+        .iter()
+        .chain(theirs.rows.iter())
+        .chain(base.rows.iter())
+        .map(|(key, _)| key)
+        .filter(|key| seen.insert(*key))
+        .collect();
+
+    // rows copied from the other side end like the header line
+    let line_ending: &[u8] = match ours.raw_headers.ends_with(b"\r\n") {
+        true => b"\r\n",
+        false => b"\n",
+    };
+
+    let mut content = ours.raw_headers.clone();
+
+    let mut conflicts = vec![];
+
+    for key in keys {
+        let (base_row, our_row, their_row) = (base.get(key), ours.get(key), theirs.get(key));
+
+        let side = if our_row == their_row || their_row == base_row {
+            &ours
+        } else if our_row == base_row {
+            &theirs
+        } else {
+            conflicts.push(key.clone());
+            continue;
+        };
+
+        if let Some(raw) = side.raw(key) {
+            // the last line of a file may have no line ending
+            if !content.is_empty() && !content.ends_with(b"\n") {
+                content.extend_from_slice(line_ending);
+            }
+
+            content.extend_from_slice(raw);
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Ok(MergeOutcome::Conflicted(conflicts));
+    }
+
+    Ok(MergeOutcome::Merged(content))
+}
+
+#[cfg(test)]
+mod test {
+    use super::merge_csv;
+    use crate::Result;
+    use crate::merge_driver::MergeOutcome;
+
+    #[test]
+    fn merge_csv_test() -> Result<()> {
+        let base = b"id,name\n1,ada\n2,alan\n";
+
+        // ours appends a row and changes one, theirs appends and deletes one
+        let ours = b"id,name\n1,ada lovelace\n2,alan\n3,grace\n";
+
+        let theirs = b"id,name\n1,ada\n4,edsger\n";
+
+        match merge_csv(Some(base), ours, theirs, None, b',')? {
+            MergeOutcome::Merged(content) => {
+                assert!(content == b"id,name\n1,ada lovelace\n3,grace\n4,edsger\n")
+            }
+            MergeOutcome::Conflicted(_) => panic!("expected a clean merge"),
+        }
+
+        // both sides change the same row differently
+        let theirs = b"id,name\n1,countess\n2,alan\n";
+
+        match merge_csv(Some(base), ours, theirs, None, b',')? {
+            MergeOutcome::Merged(_) => panic!("expected a conflict"),
+            MergeOutcome::Conflicted(keys) => assert!(keys == vec!["1".to_string()]),
+        }
+
+        // unchanged rows keep their quoting and line endings
+        let base = b"id,name\r\n1,\"ada\"\r\n2,alan\r\n";
+
+        let ours = b"id,name\r\n1,\"ada\"\r\n2,alan turing\r\n";
+
+        let theirs = b"id,name\n1,\"ada\"\n2,alan\n3,\"grace, hopper\"";
+
+        match merge_csv(Some(base), ours, theirs, None, b',')? {
+            MergeOutcome::Merged(content) => {
+                assert!(content == b"id,name\r\n1,\"ada\"\r\n2,alan turing\r\n3,\"grace, hopper\"")
+            }
+            MergeOutcome::Conflicted(_) => panic!("expected a clean merge"),
+        }
+
+        // a side that cannot be merged by key is left to a textual conflict
+        let theirs = b"id,name\n1,ada\n1,alan\n";
+
+        match merge_csv(Some(base), ours, theirs, None, b',')? {
+            MergeOutcome::Merged(_) => panic!("expected a conflict"),
+            MergeOutcome::Conflicted(keys) => assert!(keys.is_empty()),
+        }
+
+        // rows of unequal length are invalid csv
+        let theirs = b"id,name\n1,ada,lovelace\n";
+
+        match merge_csv(Some(base), ours, theirs, None, b',')? {
+            MergeOutcome::Merged(_) => panic!("expected a conflict"),
+            MergeOutcome::Conflicted(keys) => assert!(keys.is_empty()),
+        }
+
+        Ok(())
+    }
+}
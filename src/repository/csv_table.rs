@@ -0,0 +1,116 @@
+use crate::Result;
+use std::collections::{BTreeMap, HashMap};
+
+// rows of a csv file in file order, keyed by the value of the key column
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<(String, Vec<String>)>,
+    // the header line and each row as written in the file, including
+    // quoting and line endings
+    pub raw_headers: Vec<u8>,
+    raw_rows: Vec<Vec<u8>>,
+    // key to position in rows
+    index: HashMap<String, usize>,
+}
+
+impl CsvTable {
+    pub fn empty(headers: Vec<String>) -> CsvTable {
+        CsvTable {
+            headers,
+            rows: vec![],
+            raw_headers: vec![],
+            raw_rows: vec![],
+            index: HashMap::new(),
+        }
+    }
+
+    // key is a column name, None uses the first column
+    pub fn parse(content: &[u8], key: Option<&str>, delimiter: u8) -> Result<CsvTable> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .from_reader(content);
+
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+
+        let key_index = match key {
+            Some(key) => headers
+                .iter()
+                .position(|header| header == key)
+                .ok_or_else(|| crate::Error::from_message(format!("no key column `{}`", key)))?,
+            None => 0,
+        };
+
+        let mut table = CsvTable::empty(headers);
+
+        // where each row starts in content
+        let mut starts = vec![];
+
+        for record in reader.records() {
+            let record = record.map_err(csv_error)?;
+
+            let start = record.position().map_or(0, |position| position.byte() as usize);
+
+            // the reader ends a crlf line at the \r, the \n belongs to it
+            let crlf = start > 0 && content[start - 1] == b'\r' && content.get(start) == Some(&b'\n');
+
+            starts.push(match crlf {
+                true => start + 1,
+                false => start,
+            });
+
+            let values: Vec<String> = record.iter().map(|value| value.to_string()).collect();
+
+            let key = values.get(key_index).cloned().unwrap_or_default();
+
+            if table.index.contains_key(&key) {
+                return Err(crate::Error::from_message(format!(
+                    "duplicate key `{}`",
+                    key
+                )));
+            }
+
+            table.index.insert(key.clone(), table.rows.len());
+
+            table.rows.push((key, values));
+        }
+
+        // a row runs up to the start of the next one
+        let ends = starts.iter().skip(1).copied().chain([content.len()]);
+
+        table.raw_rows = starts
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| content[start..end].to_vec())
+            .collect();
+
+        table.raw_headers = content[..starts.first().copied().unwrap_or(content.len())].to_vec();
+
+        Ok(table)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Vec<String>> {
+        self.index.get(key).map(|&position| &self.rows[position].1)
+    }
+
+    pub fn raw(&self, key: &str) -> Option<&[u8]> {
+        self.index.get(key).map(|&position| self.raw_rows[position].as_slice())
+    }
+
+    // column name to value, for reporting
+    pub fn named(&self, values: &[String]) -> BTreeMap<String, String> {
+        self.headers
+            .iter()
+            .cloned()
+            .zip(values.iter().cloned())
+            .collect()
+    }
+}
+
+fn csv_error(err: csv::Error) -> crate::Error {
+    crate::Error::from_message(format!("invalid csv: {}", err))
+}
@@ -0,0 +1,9 @@
+use crate::{Repository, Result};
+
+pub fn delete_tag(repository: &Repository, name: &str) -> Result<()> {
+    repository.repo.tag_delete(name)?;
+
+    log::debug!("deleted tag `{}`", name);
+
+    Ok(())
+}
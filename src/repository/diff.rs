@@ -0,0 +1,140 @@
+use crate::file_change::{FileChange, FileChangeKind};
+use crate::{Repository, Result};
+
+pub fn diff(repository: &Repository, from: &str, to: &str) -> Result<Vec<FileChange>> {
+    let old_tree = repository.repo.revparse_single(from)?.peel_to_tree()?;
+
+    let new_tree = repository.repo.revparse_single(to)?.peel_to_tree()?;
+
+    let mut diff = repository
+        .repo
+        .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+    diff.find_similar(None)?;
+
+    let mut changes = vec![];
+
+    let mut split = false;
+
+    for delta in diff.deltas() {
+        let kind = match delta.status() {
+            git2::Delta::Added | git2::Delta::Copied => FileChangeKind::Added,
+            git2::Delta::Deleted => FileChangeKind::Deleted,
+            git2::Delta::Modified => FileChangeKind::Modified,
+            git2::Delta::Renamed => FileChangeKind::Renamed,
+            git2::Delta::Typechange => FileChangeKind::TypeChanged,
+            _ => continue,
+        };
+
+        let path = delta
+            .new_file()
+            .path()
+            .or(delta.old_file().path())
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let old_path = match kind {
+            FileChangeKind::Renamed => delta
+                .old_file()
+                .path()
+                .map(|path| path.to_string_lossy().into_owned()),
+            _ => None,
+        };
+
+        // stored forms of filtered files say nothing about how similar
+        // their contents are, only identical ones count as renamed
+        if let Some(old_path) = &old_path {
+            if delta.old_file().id() != delta.new_file().id()
+                && (repository.filters.find(repository, old_path)?.is_some()
+                    || repository.filters.find(repository, &path)?.is_some())
+            {
+                changes.push(FileChange {
+                    path: old_path.clone(),
+                    old_path: None,
+                    kind: FileChangeKind::Deleted,
+                });
+
+                changes.push(FileChange {
+                    path,
+                    old_path: None,
+                    kind: FileChangeKind::Added,
+                });
+
+                split = true;
+
+                continue;
+            }
+        }
+
+        changes.push(FileChange {
+            path,
+            old_path,
+            kind,
+        });
+    }
+
+    if split {
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::file_change::{FileChange, FileChangeKind};
+    use crate::{Repository, Result};
+    use std::fs;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn diff_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(temp_path.join("a.txt"), "a")?;
+
+        fs::write(temp_path.join("b.txt"), "b")?;
+
+        let first = repository.commit()?;
+
+        fs::write(temp_path.join("a.txt"), "changed")?;
+
+        fs::remove_file(temp_path.join("b.txt"))?;
+
+        fs::write(temp_path.join("c.txt"), "c")?;
+
+        let second = repository.commit()?;
+
+        let changes = repository.diff(&first.to_string(), &second.to_string())?;
+
+        assert!(
+            changes
+                == vec![
+                    FileChange {
+                        path: "a.txt".to_string(),
+                        old_path: None,
+                        kind: FileChangeKind::Modified,
+                    },
+                    FileChange {
+                        path: "b.txt".to_string(),
+                        old_path: None,
+                        kind: FileChangeKind::Deleted,
+                    },
+                    FileChange {
+                        path: "c.txt".to_string(),
+                        old_path: None,
+                        kind: FileChangeKind::Added,
+                    },
+                ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use super::filters::smudge;
+use crate::filter::Filter;
+use crate::{Repository, Result, Settings};
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use sha2::{Digest, Sha256};
+
+// prefixes stored ciphertext, so files committed before encryption was
+// configured are still checked out as they are
+const MAGIC: &[u8] = b"\0GIT2KIT-ENCRYPTED\0";
+
+const NONCE_LENGTH: usize = 12;
+
+// encrypts on add and decrypts on checkout, the nonce is derived from the
+// key and contents so unchanged files encrypt to the same blob
+struct EncryptionFilter {
+    key: [u8; 32],
+}
+
+impl Filter for EncryptionFilter {
+    fn clean(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        // already encrypted, but plaintext that only looks so is not stored
+        if let Some(encrypted) = content.strip_prefix(MAGIC) {
+            return match self.decrypt(encrypted) {
+                Some(_) => Ok(content.to_vec()),
+                None => Err(crate::Error::from_message(format!(
+                    "`{}` starts like encrypted content but does not decrypt",
+                    path
+                ))),
+            };
+        }
+
+        let digest = Sha256::new()
+            .chain_update(self.key)
+            .chain_update(content)
+            .finalize();
+
+        let nonce = &digest[..NONCE_LENGTH];
+
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(nonce), content)
+            .map_err(|_| crate::Error::from_message(format!("failed to encrypt `{}`", path)))?;
+
+        Ok([MAGIC, nonce, &ciphertext].concat())
+    }
+
+    fn smudge(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        let encrypted = match content.strip_prefix(MAGIC) {
+            Some(encrypted) if encrypted.len() >= NONCE_LENGTH => encrypted,
+            _ => return Ok(content.to_vec()),
+        };
+
+        self.decrypt(encrypted).ok_or_else(|| {
+            crate::Error::from_message(format!("failed to decrypt `{}`, wrong key?", path))
+        })
+    }
+}
+
+impl EncryptionFilter {
+    fn cipher(&self) -> Aes256GcmSiv {
+        Aes256GcmSiv::new(&self.key.into())
+    }
+
+    // None unless encrypted is a nonce and ciphertext for this key
+    fn decrypt(&self, encrypted: &[u8]) -> Option<Vec<u8>> {
+        if encrypted.len() < NONCE_LENGTH {
+            return None;
+        }
+
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+
+        self.cipher().decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+    }
+}
+
+// replaces the filters of earlier settings with one for the patterns of
+// every key in settings and decrypts files checked out before the keys
+// were known
+pub fn configure_encryption(repository: &mut Repository, settings: &Settings) -> Result<()> {
+    // nothing changes if any key is invalid
+    let keys = settings
+        .encryption
+        .iter()
+        .map(|encryption| parse_key(&encryption.key))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut filters: Vec<(String, Box<dyn Filter>)> = vec![];
+
+    for (encryption, key) in settings.encryption.iter().zip(keys) {
+        for pattern in &encryption.patterns {
+            filters.push((pattern.clone(), Box::new(EncryptionFilter { key })));
+        }
+    }
+
+    repository.filters.configure(filters);
+
+    if !settings.encryption.is_empty() && repository.repo.workdir().is_some() {
+        smudge(repository)?;
+    }
+
+    Ok(())
+}
+
+fn parse_key(key: &str) -> Result<[u8; 32]> {
+    let invalid = || crate::Error::from_message("encryption key must be 64 hex digits");
+
+    if key.len() != 64 || !key.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0u8; 32];
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&key[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::MAGIC;
+    use crate::{CloneOptions, EncryptionSettings, Origin, PushOptions, Repository, Result, Settings};
+    use std::fs;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn encryption_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(origin_path.to_str().unwrap(), None);
+
+        let settings = Settings {
+            encryption: vec![EncryptionSettings {
+                patterns: vec!["private/*".to_string()],
+                key: "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let ours_dir = TempDir::new();
+
+        let ours_path = ours_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut ours_repository = Repository::clone(ours_path.clone(), &origin)?;
+
+        ours_repository.configure(settings.clone())?;
+
+        fs::create_dir(ours_path.join("private"))?;
+
+        fs::write(ours_path.join("private/people.csv"), "id,name\n1,ada\n")?;
+
+        fs::write(ours_path.join("public.txt"), "hello")?;
+
+        let head = ours_repository.commit()?;
+
+        // only ciphertext is stored
+        let stored = ours_repository
+            .repo
+            .revparse_single("HEAD:private/people.csv")?
+            .peel_to_blob()?
+            .content()
+            .to_vec();
+
+        assert!(stored.starts_with(MAGIC));
+
+        assert!(!stored.windows(3).any(|window| window == b"ada"));
+
+        assert!(ours_repository.read_blob("HEAD", "public.txt")? == b"hello");
+
+        assert!(ours_repository.read_blob("HEAD", "private/people.csv")? == b"id,name\n1,ada\n");
+
+        // local work is unchanged
+        assert!(fs::read_to_string(ours_path.join("private/people.csv"))? == "id,name\n1,ada\n");
+
+        assert!(!ours_repository.working_tree_status()?.is_dirty());
+
+        assert!(ours_repository.commit()? == head);
+
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        // decrypted on clone
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut theirs_repository = Repository::clone_with(
+            theirs_path.clone(),
+            &origin,
+            &CloneOptions {
+                settings: settings.clone(),
+                ..Default::default()
+            },
+        )?;
+
+        let people = fs::read_to_string(theirs_path.join("private/people.csv"))?;
+
+        assert!(people == "id,name\n1,ada\n");
+
+        assert!(!theirs_repository.working_tree_status()?.is_dirty());
+
+        // plaintext that looks encrypted is not stored as is
+        fs::write(theirs_path.join("private/fake.csv"), [MAGIC, b"id,name\n"].concat())?;
+
+        assert!(theirs_repository.commit().is_err());
+
+        fs::remove_file(theirs_path.join("private/fake.csv"))?;
+
+        // a clone made before configuring the key decrypts once it is set
+        let late_dir = TempDir::new();
+
+        let late_path = late_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut late_repository = Repository::clone(late_path.clone(), &origin)?;
+
+        assert!(fs::read(late_path.join("private/people.csv"))?.starts_with(MAGIC));
+
+        late_repository.configure(settings)?;
+
+        let people = fs::read_to_string(late_path.join("private/people.csv"))?;
+
+        assert!(people == "id,name\n1,ada\n");
+
+        assert!(!late_repository.working_tree_status()?.is_dirty());
+
+        // configuring again replaces the keys instead of adding to them
+        ours_repository.configure(Settings::default())?;
+
+        fs::write(ours_path.join("private/plain.csv"), "id\n")?;
+
+        ours_repository.commit()?;
+
+        let stored = ours_repository
+            .repo
+            .revparse_single("HEAD:private/plain.csv")?
+            .peel_to_blob()?
+            .content()
+            .to_vec();
+
+        assert!(stored == b"id\n");
+
+        // keys must be 256 bits
+        let settings = Settings {
+            encryption: vec![EncryptionSettings {
+                patterns: vec!["*".to_string()],
+                key: "0011".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        assert!(theirs_repository.configure(settings).is_err());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use super::checkout_marker::{abandon_checkout, begin_checkout, end_checkout};
+use super::filters::filtered_checkout;
 use crate::{Repository, Result};
 use bstr::ByteSlice;
 
@@ -11,12 +13,22 @@ pub fn fast_forward(repository: &Repository, fetch_commit: git2::AnnotatedCommit
     );
 
     debug_assert!(branch.is_head());
-    repository.repo.checkout_tree(
-        &repository.repo.find_object(fetch_commit.id(), None)?,
-        Some(git2::build::CheckoutBuilder::new().safe()),
+    begin_checkout(
+        repository,
+        branch.get().name().expect("ref name is invalid utf-8"),
+        fetch_commit.id(),
     )?;
-    branch
-        .get_mut()
-        .set_target(fetch_commit.id(), &log_message)?;
+    let object = repository.repo.find_object(fetch_commit.id(), None)?;
+    filtered_checkout(repository, || {
+        repository
+            .repo
+            .checkout_tree(&object, Some(git2::build::CheckoutBuilder::new().safe()))
+            .map_err(|err| abandon_checkout(repository, err))?;
+        branch
+            .get_mut()
+            .set_target(fetch_commit.id(), &log_message)?;
+        Ok(())
+    })?;
+    end_checkout(repository)?;
     Ok(())
 }
@@ -1,26 +1,365 @@
-use crate::{Repository, Result};
+use super::head_status::REFS_TAGS_NAMESPACE;
+use crate::{Repository, Result, Settings};
+use serde::Serialize;
 
-pub fn fetch<'a>(
-    repository: &'a Repository,
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptions {
+    // overrides settings and git config when set
+    pub prune: Option<bool>,
+    // overrides settings and git config when set, only applies when pruning
+    pub prune_tags: Option<bool>,
+    // number of commits to fetch from each tip, None fetches full history
+    // or keeps a shallow repository at its current depth
+    pub depth: Option<u32>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct FetchOutcome {
+    pub updated: Vec<UpdatedRef>,
+    // names of tags that did not exist locally before the fetch
+    pub tags: Vec<String>,
+    pub pruned: Vec<String>,
+    pub received_objects: usize,
+    pub indexed_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct UpdatedRef {
+    pub reference: String,
+    // None if the ref was created by the fetch
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl FetchOutcome {
+    pub fn is_up_to_date(&self) -> bool {
+        self.updated.is_empty() && self.pruned.is_empty()
+    }
+}
+
+pub fn fetch(
+    repository: &Repository,
+    settings: &Settings,
     mut remote: git2::Remote,
-) -> Result<git2::AnnotatedCommit<'a>> {
-    let mut connect_callbacks = git2::RemoteCallbacks::new();
-    let mut fetch_callbacks = git2::RemoteCallbacks::new();
-    let mut remote_connection =
-        remote.connect_auth(git2::Direction::Fetch, Some(connect_callbacks), None)?;
-
-    remote_connection.remote().fetch::<&str>(
-        &[],
-        Some(
-            git2::FetchOptions::new()
-                .remote_callbacks(fetch_callbacks)
-                .download_tags(git2::AutotagOption::All)
-                .update_fetchhead(true),
-        ),
-        Some("git2kit: fetching"),
-    )?;
+    options: &FetchOptions,
+) -> Result<FetchOutcome> {
+    let prune = should_prune(repository, settings, &remote, options.prune)?;
+
+    let prune_tags = prune && should_prune_tags(repository, settings, &remote, options.prune_tags)?;
+
+    let refspecs = prune_refspecs(&remote, prune, prune_tags)?;
+
+    let mut outcome = FetchOutcome::default();
+
+    {
+        let mut connect_callbacks = git2::RemoteCallbacks::new();
+        let mut fetch_callbacks = git2::RemoteCallbacks::new();
+        fetch_callbacks.update_tips(|reference, old, new| {
+            // pruned refs are reported as updated to the zero oid
+            if new.is_zero() {
+                outcome.pruned.push(reference.to_owned());
+                return true;
+            }
+
+            if old.is_zero() {
+                if let Some(name) = reference.strip_prefix(REFS_TAGS_NAMESPACE) {
+                    outcome.tags.push(name.to_owned());
+                }
+            }
 
+            outcome.updated.push(UpdatedRef {
+                reference: reference.to_owned(),
+                old: match old.is_zero() {
+                    true => None,
+                    false => Some(old.to_string()),
+                },
+                new: new.to_string(),
+            });
+            true
+        });
+        let mut remote_connection =
+            remote.connect_auth(git2::Direction::Fetch, Some(connect_callbacks), None)?;
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options
+            .remote_callbacks(fetch_callbacks)
+            .download_tags(git2::AutotagOption::All)
+            .prune(match prune {
+                true => git2::FetchPrune::On,
+                false => git2::FetchPrune::Off,
+            })
+            .update_fetchhead(true);
+
+        if let Some(depth) = options.depth {
+            fetch_options.depth(git_depth(depth));
+        }
+
+        remote_connection.remote().fetch(
+            &refspecs,
+            Some(&mut fetch_options),
+            Some("git2kit: fetching"),
+        )?;
+
+        let stats = remote_connection.remote().stats();
+
+        outcome.received_objects = stats.received_objects();
+        outcome.indexed_objects = stats.indexed_objects();
+        outcome.total_objects = stats.total_objects();
+        outcome.received_bytes = stats.received_bytes();
+    }
+
+    for reference in &outcome.pruned {
+        log::debug!("fetch: pruned `{}`", reference);
+    }
+
+    Ok(outcome)
+}
+
+// libgit2 takes i32::MAX to mean the full history
+pub fn git_depth(depth: u32) -> i32 {
+    depth.min(i32::MAX as u32) as i32
+}
+
+pub fn fetch_head(repository: &Repository) -> Result<git2::AnnotatedCommit<'_>> {
+    // this errors when the remote is empty
     let fetch_head = repository.repo.find_reference("FETCH_HEAD")?;
 
     Ok(repository.repo.reference_to_annotated_commit(&fetch_head)?)
 }
+
+// the per-call override wins over settings, which win over git config
+pub fn should_prune(
+    repository: &Repository,
+    settings: &Settings,
+    remote: &git2::Remote,
+    prune: Option<bool>,
+) -> Result<bool> {
+    if let Some(prune) = prune.or(settings.prune) {
+        return Ok(prune);
+    }
+
+    let config = repository.repo.config()?.snapshot()?;
+
+    if let Some(name) = remote.name() {
+        if let Ok(prune) = config.get_bool(&format!("remote.{}.prune", name)) {
+            return Ok(prune);
+        }
+    }
+
+    Ok(config.get_bool("fetch.prune").unwrap_or(false))
+}
+
+// like `fetch.pruneTags`, local tags are kept unless asked for
+pub fn should_prune_tags(
+    repository: &Repository,
+    settings: &Settings,
+    remote: &git2::Remote,
+    prune_tags: Option<bool>,
+) -> Result<bool> {
+    if let Some(prune_tags) = prune_tags.or(settings.prune_tags) {
+        return Ok(prune_tags);
+    }
+
+    let config = repository.repo.config()?.snapshot()?;
+
+    if let Some(name) = remote.name() {
+        if let Ok(prune_tags) = config.get_bool(&format!("remote.{}.pruneTags", name)) {
+            return Ok(prune_tags);
+        }
+    }
+
+    Ok(config.get_bool("fetch.pruneTags").unwrap_or(false))
+}
+
+// libgit2 only prunes refs matching the refspecs of the fetch,
+// so tags need their own refspec to be pruned like `--prune-tags`
+pub fn prune_refspecs(remote: &git2::Remote, prune: bool, prune_tags: bool) -> Result<Vec<String>> {
+    if !prune {
+        return Ok(vec![]);
+    }
+
+    let mut refspecs: Vec<String> = remote
+        .fetch_refspecs()?
+        .iter()
+        .flatten()
+        .map(|refspec| refspec.to_owned())
+        .collect();
+
+    if prune_tags {
+        refspecs.push(format!("+{0}*:{0}*", REFS_TAGS_NAMESPACE));
+    }
+
+    Ok(refspecs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FetchOptions, UpdatedRef};
+    use crate::{PushOptions, Repository, Origin, Result, Settings};
+    use crate::repository::UpstreamStatus;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn fetch_prune_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let push_dir = TempDir::new();
+
+        let push_path = push_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut push_repository = Repository::clone(push_path.clone(), &origin)?;
+
+        let oid = push_repository.commit()?;
+
+        push_repository.repo.branch("dev", &push_repository.repo.find_commit(oid)?, false)?;
+
+        push_repository.create_tag("v1", "HEAD", None)?;
+
+        push_repository.push(&origin, &PushOptions {
+            all_branches: true,
+            tags: true,
+            set_upstream: true,
+            ..PushOptions::default()
+        })?;
+
+        push_repository.switch_branch("dev", false)?;
+
+        // the branch and the tag are deleted on the server
+        origin_repository.repo.find_reference("refs/heads/dev")?.delete()?;
+
+        origin_repository.repo.tag_delete("v1")?;
+
+        // a fetch without pruning keeps the stale refs
+        let options = FetchOptions {
+            prune: Some(false),
+            ..Default::default()
+        };
+
+        let outcome = push_repository.fetch(&origin, &options)?;
+
+        assert!(outcome.pruned.is_empty());
+
+        let head = push_repository.head_status()?;
+
+        assert!(matches!(push_repository.upstream_status(&head)?, UpstreamStatus::Upstream { .. }));
+
+        // a tag that was never pushed
+        push_repository.create_tag("local", "HEAD", None)?;
+
+        // pruning is enabled by settings and keeps tags by default
+        push_repository.configure(Settings {
+            prune: Some(true),
+            ..Default::default()
+        })?;
+
+        let outcome = push_repository.fetch(&origin, &FetchOptions::default())?;
+
+        assert!(outcome.pruned == vec!["refs/remotes/origin/dev".to_string()]);
+
+        assert!(push_repository.repo.find_reference("refs/remotes/origin/main").is_ok());
+
+        assert!(push_repository.repo.find_reference("refs/tags/v1").is_ok());
+
+        assert!(push_repository.repo.find_reference("refs/tags/local").is_ok());
+
+        // tags missing on the server are pruned only when asked for
+        let options = FetchOptions {
+            prune_tags: Some(true),
+            ..Default::default()
+        };
+
+        let outcome = push_repository.fetch(&origin, &options)?;
+
+        assert!(outcome.pruned.contains(&"refs/tags/v1".to_string()));
+
+        assert!(push_repository.repo.find_reference("refs/tags/v1").is_err());
+
+        assert!(matches!(push_repository.upstream_status(&head)?, UpstreamStatus::Gone));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_outcome_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let push_dir = TempDir::new();
+
+        let push_path = push_dir.as_ref().unwrap().path().to_path_buf();
+
+        let push_repository = Repository::clone(push_path.clone(), &origin)?;
+
+        let first = push_repository.commit()?;
+
+        push_repository.push(&origin, &PushOptions::default())?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        let fetch_dir = TempDir::new();
+
+        let fetch_path = fetch_dir.as_ref().unwrap().path().to_path_buf();
+
+        let fetch_repository = Repository::clone(fetch_path.clone(), &origin)?;
+
+        let mut file = File::create(push_path.join("foo.txt"))?;
+
+        file.write_all(b"Hello, world!")?;
+
+        let second = push_repository.commit()?;
+
+        push_repository.create_tag("v1", "HEAD", Some("first release"))?;
+
+        push_repository.push(&origin, &PushOptions {
+            tags: true,
+            ..PushOptions::default()
+        })?;
+
+        let outcome = fetch_repository.fetch(&origin, &FetchOptions::default())?;
+
+        assert!(!outcome.is_up_to_date());
+
+        assert!(outcome.updated.contains(&UpdatedRef {
+            reference: "refs/remotes/origin/main".to_string(),
+            old: Some(first.to_string()),
+            new: second.to_string(),
+        }));
+
+        assert!(outcome.tags == vec!["v1".to_string()]);
+
+        assert!(outcome.received_objects > 0);
+
+        assert!(outcome.received_bytes > 0);
+
+        // nothing changed on the server since
+        let outcome = fetch_repository.fetch(&origin, &FetchOptions::default())?;
+
+        assert!(outcome.is_up_to_date());
+
+        Ok(())
+    }
+}
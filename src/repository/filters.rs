@@ -0,0 +1,783 @@
+use super::merge_drivers::INDEX_STAGE_MASK;
+use super::registry::Registry;
+use crate::filter::Filter;
+use crate::{Repository, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+pub type Filters = Registry<dyn Filter>;
+
+// the index entry mode of a submodule
+const SUBMODULE_MODE: u32 = 0o160000;
+
+impl Default for Filters {
+    fn default() -> Self {
+        Filters::new("filter")
+    }
+}
+
+// content to store for a path, unchanged if no filter applies
+pub fn clean(repository: &Repository, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+    match repository.filters.find(repository, path)? {
+        Some(filter) => filter.clean(path, content),
+        None => Ok(content.to_vec()),
+    }
+}
+
+// content to write to the working tree for a path, unchanged if no
+// filter applies
+pub fn smudge_content(repository: &Repository, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+    match repository.filters.find(repository, path)? {
+        Some(filter) => filter.smudge(path, content),
+        None => Ok(content.to_vec()),
+    }
+}
+
+// stages a filtered working tree file in its cleaned form with the
+// file's stat, storing a blob only if the content changed, and returns
+// whether it did
+pub fn stage_filtered(
+    repository: &Repository,
+    index: &mut git2::Index,
+    path: &str,
+) -> Result<bool> {
+    let filter = match repository.filters.find(repository, path)? {
+        Some(filter) => filter,
+        None => return Err(crate::Error::from_message(format!("`{}` is not filtered", path))),
+    };
+
+    let file_path = workdir(repository)?.join(path);
+
+    let metadata = fs::metadata(&file_path)?;
+
+    let staged = index.get_path(Path::new(path), 0);
+
+    // unchanged since it was staged or checked out
+    if let Some(staged) = &staged {
+        if Stat::of_entry(staged) == Stat::of_file(&metadata) {
+            return Ok(false);
+        }
+    }
+
+    let content = fs::read(&file_path)?;
+
+    let oid = git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        &filter.clean_for_compare(path, &content)?,
+    )?;
+
+    let changed = staged.as_ref().map(|staged| staged.id) != Some(oid);
+
+    if changed {
+        repository.repo.blob(&filter.clean(path, &content)?)?;
+    }
+
+    let mut entry = git2::IndexEntry {
+        ctime: git2::IndexTime::new(0, 0),
+        mtime: git2::IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: file_mode(&metadata),
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: oid,
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    };
+
+    Stat::of_file(&metadata).apply(&mut entry);
+
+    // staging resolves a conflict, as `git add` does
+    match index.conflict_remove(Path::new(path)) {
+        Ok(()) => (),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => (),
+        Err(err) => return Err(err.into()),
+    }
+
+    index.add(&entry)?;
+
+    Ok(changed)
+}
+
+// whether a file libgit2 sees as modified still cleans to what is staged
+pub fn is_unchanged_after_clean(repository: &Repository, path: &str) -> Result<bool> {
+    let filter = match repository.filters.find(repository, path)? {
+        Some(filter) => filter,
+        None => return Ok(false),
+    };
+
+    let mut index = repository.repo.index()?;
+
+    index.read(false)?;
+
+    let entry = match index.get_path(Path::new(path), 0) {
+        Some(entry) => entry,
+        None => return Ok(false),
+    };
+
+    let content = match fs::read(workdir(repository)?.join(path)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+
+    let cleaned = filter.clean_for_compare(path, &content)?;
+
+    Ok(git2::Oid::hash_object(git2::ObjectType::Blob, &cleaned)? == entry.id)
+}
+
+// runs a checkout, then smudges the filtered files it wrote in their
+// stored form; the index has the stat of the smudged files, so libgit2
+// takes them as unmodified and never rewrites the ones it leaves alone.
+// Refs the checkout moves are moved inside it, so a failed smudge leaves
+// a finished checkout that recover smudges again
+pub fn filtered_checkout<T>(
+    repository: &Repository,
+    checkout: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    if repository.filters.is_empty() {
+        return checkout();
+    }
+
+    let before = refresh(repository)?;
+
+    let result = checkout();
+
+    // a failed checkout may still have written files
+    match (result, smudge_changed(repository, &before)) {
+        (Ok(value), Ok(_)) => Ok(value),
+        (Ok(_), Err(err)) => Err(err),
+        (Err(err), smudged) => {
+            if let Err(smudge_err) = smudged {
+                log::warn!("checkout: failed to smudge files: {}", smudge_err);
+            }
+            Err(err)
+        }
+    }
+}
+
+// smudges files the working tree has in their stored form, such as after
+// a clone or an interrupted checkout, and returns their paths
+pub fn smudge(repository: &Repository) -> Result<Vec<String>> {
+    if repository.filters.is_empty() {
+        return Ok(vec![]);
+    }
+
+    smudge_changed(repository, &HashMap::new())
+}
+
+// the stat libgit2 compares to take a file as unchanged without reading it
+#[derive(PartialEq)]
+struct Stat {
+    ctime: git2::IndexTime,
+    mtime: git2::IndexTime,
+    dev: u32,
+    ino: u32,
+    uid: u32,
+    gid: u32,
+    file_size: u32,
+}
+
+impl Stat {
+    fn of_entry(entry: &git2::IndexEntry) -> Stat {
+        Stat {
+            ctime: entry.ctime,
+            mtime: entry.mtime,
+            dev: entry.dev,
+            ino: entry.ino,
+            uid: entry.uid,
+            gid: entry.gid,
+            file_size: entry.file_size,
+        }
+    }
+
+    // truncated like libgit2 truncates them
+    #[cfg(unix)]
+    fn of_file(metadata: &fs::Metadata) -> Stat {
+        use std::os::unix::fs::MetadataExt;
+
+        Stat {
+            ctime: git2::IndexTime::new(metadata.ctime() as i32, metadata.ctime_nsec() as u32),
+            mtime: git2::IndexTime::new(metadata.mtime() as i32, metadata.mtime_nsec() as u32),
+            dev: metadata.dev() as u32,
+            ino: metadata.ino() as u32,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            file_size: metadata.size() as u32,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn of_file(metadata: &fs::Metadata) -> Stat {
+        let mtime = index_time(metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+
+        Stat {
+            ctime: mtime,
+            mtime,
+            dev: 0,
+            ino: 0,
+            uid: 0,
+            gid: 0,
+            file_size: metadata.len() as u32,
+        }
+    }
+
+    // matches no file, so libgit2 compares the contents instead
+    fn unknown() -> Stat {
+        Stat {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+        }
+    }
+
+    fn apply(&self, entry: &mut git2::IndexEntry) {
+        entry.ctime = self.ctime;
+        entry.mtime = self.mtime;
+        entry.dev = self.dev;
+        entry.ino = self.ino;
+        entry.uid = self.uid;
+        entry.gid = self.gid;
+        entry.file_size = self.file_size;
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    match metadata.permissions().mode() & 0o111 {
+        0 => 0o100644,
+        _ => 0o100755,
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o100644
+}
+
+// records the stat of filtered files that changed on disk but still
+// clean to what is staged, and returns what the index has for each
+fn refresh(repository: &Repository) -> Result<HashMap<String, (git2::Oid, Stat)>> {
+    let workdir = workdir(repository)?;
+
+    let mut index = read_index(repository)?;
+
+    let mut staged = HashMap::new();
+
+    let mut refreshed = vec![];
+
+    for (path, filter, mut entry) in filtered_entries(repository, &index)? {
+        let file_path = workdir.join(&path);
+
+        if let Some(metadata) = metadata(&file_path)? {
+            let stat = Stat::of_file(&metadata);
+
+            if Stat::of_entry(&entry) != stat {
+                let cleaned = filter.clean_for_compare(&path, &fs::read(&file_path)?)?;
+
+                if git2::Oid::hash_object(git2::ObjectType::Blob, &cleaned)? == entry.id {
+                    stat.apply(&mut entry);
+
+                    index.add(&entry)?;
+
+                    refreshed.push(path.clone());
+                }
+            }
+        }
+
+        staged.insert(path, (entry.id, Stat::of_entry(&entry)));
+    }
+
+    if !refreshed.is_empty() {
+        write_index(&mut index, &refreshed)?;
+    }
+
+    Ok(staged)
+}
+
+// smudges the filtered files whose index entry is not the one in before
+// and whose working tree file has the stored form
+fn smudge_changed(
+    repository: &Repository,
+    before: &HashMap<String, (git2::Oid, Stat)>,
+) -> Result<Vec<String>> {
+    let workdir = workdir(repository)?;
+
+    let mut index = read_index(repository)?;
+
+    let mut smudged = vec![];
+
+    for (path, filter, mut entry) in filtered_entries(repository, &index)? {
+        if before.get(&path) == Some(&(entry.id, Stat::of_entry(&entry))) {
+            continue;
+        }
+
+        let file_path = workdir.join(&path);
+
+        let current = match fs::read(&file_path) {
+            Ok(current) => current,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        let stored = repository.repo.find_blob(entry.id)?;
+
+        // locally modified, or smudged already
+        if current != stored.content() {
+            continue;
+        }
+
+        let content = filter.smudge(&path, stored.content())?;
+
+        if content == current {
+            continue;
+        }
+
+        let metadata = write_file(&file_path, &content)?;
+
+        Stat::of_file(&metadata).apply(&mut entry);
+
+        index.add(&entry)?;
+
+        smudged.push(path);
+    }
+
+    if !smudged.is_empty() {
+        write_index(&mut index, &smudged)?;
+    }
+
+    Ok(smudged)
+}
+
+// stage 0 index entries of filtered files with their filter
+fn filtered_entries<'a>(
+    repository: &'a Repository,
+    index: &git2::Index,
+) -> Result<Vec<(String, &'a dyn Filter, git2::IndexEntry)>> {
+    let mut entries = vec![];
+
+    for entry in index.iter() {
+        // conflict stages and submodules have nothing to filter
+        if entry.flags & INDEX_STAGE_MASK != 0 || entry.mode == SUBMODULE_MODE {
+            continue;
+        }
+
+        let path = String::from_utf8_lossy(&entry.path).into_owned();
+
+        if let Some(filter) = repository.filters.find(repository, &path)? {
+            entries.push((path, filter, entry));
+        }
+    }
+
+    Ok(entries)
+}
+
+fn read_index(repository: &Repository) -> Result<git2::Index> {
+    let mut index = repository.repo.index()?;
+
+    // another handle may have checked out, such as for stashes
+    index.read(false)?;
+
+    Ok(index)
+}
+
+fn metadata(path: &Path) -> Result<Option<fs::Metadata>> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// replaces a file through a temporary one, so it is never half written
+fn write_file(path: &Path, content: &[u8]) -> Result<fs::Metadata> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let temp_path = path.with_file_name(format!(".{}.git2kit", name));
+
+    fs::write(&temp_path, content)?;
+
+    fs::set_permissions(&temp_path, fs::metadata(path)?.permissions())?;
+
+    fs::rename(&temp_path, path)?;
+
+    Ok(fs::metadata(path)?)
+}
+
+// how long writing the index waits to be newer than the files it records,
+// enough for file systems with coarse timestamps
+const RACY_WAIT: Duration = Duration::from_millis(100);
+
+// writes an index with the stat of filtered files at paths once it is
+// newer than them, libgit2 rehashes entries as new as the index and would
+// hash the smudged form; adding them again keeps libgit2 from doing so on
+// the write itself. Files the index does not get newer than in time, such
+// as ones dated in the future, are left with a stat that matches no file
+pub fn write_index(index: &mut git2::Index, paths: &[String]) -> Result<()> {
+    let mut entries = vec![];
+
+    for path in paths {
+        if let Some(entry) = index.get_path(Path::new(path), 0) {
+            entries.push(entry);
+        }
+    }
+
+    let newest = match entries.iter().map(|entry| entry.mtime).max() {
+        Some(newest) => newest,
+        None => {
+            index.write()?;
+            return Ok(());
+        }
+    };
+
+    let deadline = SystemTime::now() + RACY_WAIT;
+
+    let written = loop {
+        for entry in &entries {
+            index.add(entry)?;
+        }
+
+        index.write()?;
+
+        let index_path = index
+            .path()
+            .ok_or_else(|| crate::Error::from_message("index has no file"))?;
+
+        let written = index_time(fs::metadata(index_path)?.modified()?);
+
+        if written > newest {
+            return Ok(());
+        }
+
+        if SystemTime::now() >= deadline || newest > index_time(deadline) {
+            break written;
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    };
+
+    for entry in &mut entries {
+        if entry.mtime >= written {
+            Stat::unknown().apply(entry);
+            index.add(entry)?;
+        }
+    }
+
+    index.write()?;
+
+    Ok(())
+}
+
+// truncated like libgit2 truncates them
+fn index_time(time: SystemTime) -> git2::IndexTime {
+    let time = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+
+    git2::IndexTime::new(time.as_secs() as i32, time.subsec_nanos())
+}
+
+fn workdir(repository: &Repository) -> Result<&Path> {
+    repository
+        .repo
+        .workdir()
+        .ok_or_else(|| crate::Error::from_message("repository has no working tree"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::file_change::FileChangeKind;
+    use crate::{CommitBuilder, Filter, Repository, Result};
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use temp_dir::TempDir;
+
+    // stores contents without the prefix the working tree has
+    struct PrefixFilter;
+
+    impl Filter for PrefixFilter {
+        fn clean(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            Ok(content
+                .strip_prefix(b"SMUDGED:")
+                .unwrap_or(content)
+                .to_vec())
+        }
+
+        fn smudge(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            Ok([b"SMUDGED:", content].concat())
+        }
+    }
+
+    struct BrokenFilter;
+
+    impl Filter for BrokenFilter {
+        fn clean(&self, path: &str, _content: &[u8]) -> Result<Vec<u8>> {
+            Err(crate::Error::from_message(format!("cannot clean `{}`", path)))
+        }
+
+        fn smudge(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            Ok(content.to_vec())
+        }
+    }
+
+    // smudges like PrefixFilter unless told to fail
+    struct FlakyFilter(Arc<AtomicBool>);
+
+    impl Filter for FlakyFilter {
+        fn clean(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            PrefixFilter.clean(path, content)
+        }
+
+        fn smudge(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            match self.0.load(Ordering::SeqCst) {
+                true => Err(crate::Error::from_message(format!("cannot smudge `{}`", path))),
+                false => PrefixFilter.smudge(path, content),
+            }
+        }
+    }
+
+    #[test]
+    fn filters_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        repository.register_filter_for("*.secret", PrefixFilter);
+
+        repository.register_filter("prefix", PrefixFilter);
+
+        fs::write(
+            temp_path.join(".gitattributes"),
+            "notes.txt filter=prefix\n",
+        )?;
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:hello")?;
+
+        fs::write(temp_path.join("notes.txt"), "SMUDGED:notes")?;
+
+        let first = repository.commit()?;
+
+        // cleaned on add, without storing the working tree contents
+        assert!(stored(&repository, "HEAD:a.secret")? == b"hello");
+
+        assert!(stored(&repository, "HEAD:notes.txt")? == b"notes");
+
+        let smudged = git2::Oid::hash_object(git2::ObjectType::Blob, b"SMUDGED:hello")?;
+
+        assert!(!repository.repo.odb()?.exists(smudged));
+
+        // read as checked out
+        assert!(repository.read_blob("HEAD", "a.secret")? == b"SMUDGED:hello");
+
+        let entries = repository.list_tree("HEAD", None, false)?;
+
+        assert!(entries.iter().any(|entry| entry.path == "a.secret" && entry.size == Some(13)));
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        // nothing changed, so nothing to commit
+        assert!(repository.commit()? == first);
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:world")?;
+
+        repository.commit()?;
+
+        // smudged on checkout
+        repository
+            .repo
+            .branch("old", &repository.repo.find_commit(first)?, false)?;
+
+        let notes = fs::metadata(temp_path.join("notes.txt"))?.modified()?;
+
+        repository.switch_branch("old", false)?;
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "SMUDGED:hello");
+
+        // files the checkout does not change are left alone
+        assert!(fs::metadata(temp_path.join("notes.txt"))?.modified()? == notes);
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        repository.switch_branch("main", false)?;
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "SMUDGED:world");
+
+        // local edits block the checkout as usual
+        fs::write(temp_path.join("a.secret"), "SMUDGED:edited")?;
+
+        assert!(repository.switch_branch("old", false).is_err());
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "SMUDGED:edited");
+
+        // commits without a working tree are cleaned too
+        let mut builder = CommitBuilder::new("main", "add b");
+
+        builder.write("b.secret", "SMUDGED:b");
+
+        let third = repository.commit_to_branch(&builder)?;
+
+        assert!(stored(&repository, &format!("{}:b.secret", third))? == b"b");
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_future_mtime_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        repository.register_filter_for("*.secret", PrefixFilter);
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:hello")?;
+
+        // the index never gets newer than a file dated a day ahead
+        fs::File::options()
+            .write(true)
+            .open(temp_path.join("a.secret"))?
+            .set_modified(SystemTime::now() + Duration::from_secs(86400))?;
+
+        let first = repository.commit()?;
+
+        assert!(stored(&repository, "HEAD:a.secret")? == b"hello");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        assert!(repository.commit()? == first);
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_smudge_failure_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        let fail = Arc::new(AtomicBool::new(false));
+
+        repository.register_filter_for("*.secret", FlakyFilter(fail.clone()));
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:first")?;
+
+        let first = repository.commit()?;
+
+        repository
+            .repo
+            .branch("old", &repository.repo.find_commit(first)?, false)?;
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:second")?;
+
+        repository.commit()?;
+
+        fail.store(true, Ordering::SeqCst);
+
+        // the checkout and HEAD go through, the smudge is left to recover
+        assert!(repository.switch_branch("old", false).is_err());
+
+        assert!(repository.head_branch()?.name()? == Some("old"));
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "first");
+
+        fail.store(false, Ordering::SeqCst);
+
+        let recovery = repository.recover(Duration::ZERO)?;
+
+        assert!(recovery.completed_checkout == Some("refs/heads/old".to_string()));
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "SMUDGED:first");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_revert_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        repository.register_filter_for("*.secret", PrefixFilter);
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:first")?;
+
+        repository.commit()?;
+
+        fs::write(temp_path.join("a.secret"), "SMUDGED:second")?;
+
+        repository.commit()?;
+
+        repository.revert("HEAD", None)?.unwrap();
+
+        assert!(fs::read_to_string(temp_path.join("a.secret"))? == "SMUDGED:first");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        // renamed and changed, stored forms cannot tell
+        fs::rename(temp_path.join("a.secret"), temp_path.join("b.secret"))?;
+
+        fs::write(temp_path.join("b.secret"), "SMUDGED:firsts")?;
+
+        repository.commit()?;
+
+        let changes = repository.diff("HEAD~1", "HEAD")?;
+
+        assert!(changes.len() == 2);
+
+        assert!(changes[0].kind == FileChangeKind::Deleted);
+
+        assert!(changes[1].kind == FileChangeKind::Added);
+
+        // a file an interrupted checkout left stored is smudged on recover
+        fs::write(temp_path.join("b.secret"), "firsts")?;
+
+        let recovery = repository.recover(Duration::from_secs(0))?;
+
+        assert!(recovery.smudged_files == vec!["b.secret".to_string()]);
+
+        assert!(fs::read_to_string(temp_path.join("b.secret"))? == "SMUDGED:firsts");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        // a failing filter fails the commit
+        repository.register_filter_for("*.broken", BrokenFilter);
+
+        fs::write(temp_path.join("c.broken"), "c")?;
+
+        assert!(repository.commit().is_err());
+
+        Ok(())
+    }
+
+    // the blob stored at a revision and path, as `git cat-file`
+    fn stored(repository: &Repository, spec: &str) -> Result<Vec<u8>> {
+        Ok(repository
+            .repo
+            .revparse_single(spec)?
+            .peel_to_blob()?
+            .content()
+            .to_vec())
+    }
+}
@@ -6,6 +6,7 @@ use bstr::ByteSlice;
 
 pub const HEAD_FILE: &str = "HEAD";
 pub const REFS_HEADS_NAMESPACE: &str = "refs/heads/";
+pub const REFS_TAGS_NAMESPACE: &str = "refs/tags/";
 
 pub fn head_status(repository: &Repository) -> Result<HeadStatus> {
     let head = repository.repo.find_reference(HEAD_FILE)?;
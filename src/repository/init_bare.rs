@@ -3,7 +3,13 @@ use std::path::Path;
 
 pub fn init_bare(path: &Path) -> Result<Repository> {
     match git2::Repository::init_bare(&path) {
-        Ok(repo) => Ok(Repository { repo }),
+        Ok(repo) => Ok(Repository {
+            repo,
+            merge_drivers: Default::default(),
+            filters: Default::default(),
+            lfs_store: None,
+            settings: Default::default(),
+        }),
         Err(e) => panic!("failed to init: {}", e),
     }
 }
@@ -0,0 +1,409 @@
+use super::filters::smudge;
+use crate::filter::Filter;
+use crate::http_lfs_store::HttpLfsStore;
+use crate::lfs_store::{FileSystemLfsStore, LfsStore};
+use crate::{Origin, Repository, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+// pointers are around 130 bytes, larger blobs are not read when scanning
+const MAX_POINTER_SIZE: usize = 1024;
+
+// replaces file contents with a pointer on add, keeping the contents in
+// .git/lfs/objects, and swaps pointers back on checkout
+struct LfsFilter {
+    local: FileSystemLfsStore,
+    remote: Arc<dyn LfsStore>,
+}
+
+impl Filter for LfsFilter {
+    fn clean(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        if parse_pointer(content).is_some() {
+            return Ok(content.to_vec());
+        }
+
+        let oid = format!("{:x}", Sha256::digest(content));
+
+        if !self.local.contains(&oid, content.len() as u64)? {
+            self.local.upload(&oid, content)?;
+        }
+
+        Ok(pointer(&oid, content.len()))
+    }
+
+    fn clean_for_compare(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        if parse_pointer(content).is_some() {
+            return Ok(content.to_vec());
+        }
+
+        Ok(pointer(&format!("{:x}", Sha256::digest(content)), content.len()))
+    }
+
+    fn smudge(&self, path: &str, content: &[u8]) -> Result<Vec<u8>> {
+        let (oid, size) = match parse_pointer(content) {
+            Some(pointer) => pointer,
+            None => return Ok(content.to_vec()),
+        };
+
+        if self.local.contains(&oid, size)? {
+            return self.local.download(&oid, size);
+        }
+
+        log::debug!("lfs: downloading `{}` for `{}`", oid, path);
+
+        let object = self.remote.download(&oid, size)?;
+
+        if object.len() as u64 != size || format!("{:x}", Sha256::digest(&object)) != oid {
+            return Err(crate::Error::from_message(format!(
+                "LFS object `{}` for `{}` is corrupt",
+                oid, path
+            )));
+        }
+
+        self.local.upload(&oid, &object)?;
+
+        Ok(object)
+    }
+}
+
+fn pointer(oid: &str, size: usize) -> Vec<u8> {
+    format!("{}\noid sha256:{}\nsize {}\n", POINTER_VERSION, oid, size).into_bytes()
+}
+
+// the sha256 and size of the object a pointer file refers to
+fn parse_pointer(content: &[u8]) -> Option<(String, u64)> {
+    if content.len() > MAX_POINTER_SIZE {
+        return None;
+    }
+
+    let text = std::str::from_utf8(content).ok()?;
+
+    let mut lines = text.lines();
+
+    if lines.next()? != POINTER_VERSION {
+        return None;
+    }
+
+    let (mut oid, mut size) = (None, None);
+
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+
+    Some((oid?, size?))
+}
+
+// files with the `filter=lfs` attribute are stored through store, and
+// pointers already checked out are downloaded
+pub fn enable_lfs(repository: &mut Repository, store: Arc<dyn LfsStore>) -> Result<()> {
+    let local = FileSystemLfsStore::new(repository.repo.path().join("lfs").join("objects"));
+
+    repository.lfs_store = Some(store.clone());
+
+    repository.register_filter(
+        "lfs",
+        LfsFilter {
+            local,
+            remote: store,
+        },
+    );
+
+    if repository.repo.workdir().is_some() {
+        smudge(repository)?;
+    }
+
+    Ok(())
+}
+
+// the LFS server of origin, lfs.url in the repository config takes
+// precedence like it does for git-lfs
+pub fn http_lfs_store(repository: &Repository, origin: &Origin) -> Result<HttpLfsStore> {
+    let config = repository.repo.config()?.snapshot()?;
+
+    match config.get_str("lfs.url") {
+        Ok(url) => Ok(HttpLfsStore::new(url, origin.token.as_deref())),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => HttpLfsStore::for_origin(origin),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// uploads the objects of pointers in commits the remote does not have
+// yet, returns the uploaded object ids
+pub fn upload_lfs_objects(
+    repository: &Repository,
+    remote_name: Option<&str>,
+    references: &[String],
+) -> Result<Vec<String>> {
+    let store = match &repository.lfs_store {
+        Some(store) => store,
+        None => return Ok(vec![]),
+    };
+
+    let local = FileSystemLfsStore::new(repository.repo.path().join("lfs").join("objects"));
+
+    let mut walk = repository.repo.revwalk()?;
+
+    for reference in references {
+        walk.push(
+            repository
+                .repo
+                .revparse_single(reference)?
+                .peel_to_commit()?
+                .id(),
+        )?;
+    }
+
+    if let Some(remote_name) = remote_name {
+        walk.hide_glob(&format!("refs/remotes/{}/*", remote_name))?;
+    }
+
+    let odb = repository.repo.odb()?;
+
+    let mut trees = HashSet::new();
+
+    let mut pointers = HashSet::new();
+
+    for oid in walk {
+        let tree = repository.repo.find_commit(oid?)?.tree()?;
+
+        find_pointers(repository, &odb, &tree, "", &mut trees, &mut pointers)?;
+    }
+
+    let mut uploaded = vec![];
+
+    for blob in pointers {
+        let blob = repository.repo.find_blob(blob)?;
+
+        let (oid, size) = match parse_pointer(blob.content()) {
+            Some(pointer) => pointer,
+            None => continue,
+        };
+
+        if uploaded.contains(&oid) || store.contains(&oid, size)? {
+            continue;
+        }
+
+        if !local.contains(&oid, size)? {
+            return Err(crate::Error::from_message(format!(
+                "LFS object `{}` is missing locally",
+                oid
+            )));
+        }
+
+        log::debug!("lfs: uploading `{}`", oid);
+
+        store.upload(&oid, &local.download(&oid, size)?)?;
+
+        uploaded.push(oid);
+    }
+
+    Ok(uploaded)
+}
+
+// collects blobs small enough to be pointers at `filter=lfs` paths of
+// trees not in trees yet
+fn find_pointers(
+    repository: &Repository,
+    odb: &git2::Odb,
+    tree: &git2::Tree,
+    prefix: &str,
+    trees: &mut HashSet<git2::Oid>,
+    pointers: &mut HashSet<git2::Oid>,
+) -> Result<()> {
+    if !trees.insert(tree.id()) {
+        return Ok(());
+    }
+
+    for entry in tree.iter() {
+        let path = format!("{}{}", prefix, entry.name().unwrap_or_default());
+
+        match entry.kind() {
+            Some(git2::ObjectType::Tree) => {
+                let subtree = repository.repo.find_tree(entry.id())?;
+
+                find_pointers(repository, odb, &subtree, &format!("{}/", path), trees, pointers)?;
+            }
+            Some(git2::ObjectType::Blob) if !pointers.contains(&entry.id()) => {
+                let attribute = repository.repo.get_attr(
+                    Path::new(&path),
+                    "filter",
+                    git2::AttrCheckFlags::FILE_THEN_INDEX,
+                )?;
+
+                if attribute == Some("lfs") && odb.read_header(entry.id())?.0 <= MAX_POINTER_SIZE {
+                    pointers.insert(entry.id());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_pointer, upload_lfs_objects};
+    use crate::{
+        CloneOptions, FileSystemLfsStore, LfsStore, Origin, PullOptions, PushOptions, Repository,
+        Result,
+    };
+    use sha2::{Digest, Sha256};
+    use std::fs;
+    use std::sync::Arc;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn lfs_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let store_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let store_path = store_dir.as_ref().unwrap().path().to_path_buf();
+
+        let store = FileSystemLfsStore::new(&store_path);
+
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(origin_path.to_str().unwrap(), None);
+
+        let ours_dir = TempDir::new();
+
+        let ours_path = ours_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut ours_repository = Repository::clone(ours_path.clone(), &origin)?;
+
+        ours_repository.enable_lfs(FileSystemLfsStore::new(&store_path))?;
+
+        fs::write(ours_path.join(".gitattributes"), "*.bin filter=lfs\n")?;
+
+        let photo: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+
+        fs::write(ours_path.join("photo.bin"), &photo)?;
+
+        ours_repository.commit()?;
+
+        // a pointer is committed and the contents kept locally
+        let pointer = ours_repository
+            .repo
+            .revparse_single("HEAD:photo.bin")?
+            .peel_to_blob()?
+            .content()
+            .to_vec();
+
+        assert!(ours_repository.read_blob("HEAD", "photo.bin")? == photo);
+
+        let (oid, size) = parse_pointer(&pointer).unwrap();
+
+        assert!(size == 4096);
+
+        assert!(
+            ours_path
+                .join(".git/lfs/objects")
+                .join(&oid[0..2])
+                .join(&oid[2..4])
+                .join(&oid)
+                .is_file()
+        );
+
+        assert!(!ours_repository.working_tree_status()?.is_dirty());
+
+        assert!(!store.contains(&oid, size)?);
+
+        // uploaded on push
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        assert!(store.download(&oid, size)? == photo);
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        // downloaded on clone
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_repository = Repository::clone_with(
+            theirs_path.clone(),
+            &origin,
+            &CloneOptions {
+                lfs_store: Some(Arc::new(FileSystemLfsStore::new(&store_path))),
+                ..Default::default()
+            },
+        )?;
+
+        assert!(fs::read(theirs_path.join("photo.bin"))? == photo);
+
+        assert!(!theirs_repository.working_tree_status()?.is_dirty());
+
+        // only objects of commits the remote branch does not have are
+        // uploaded, and only for `filter=lfs` paths
+        let edited: Vec<u8> = photo.iter().rev().cloned().collect();
+
+        fs::write(ours_path.join("photo.bin"), &edited)?;
+
+        fs::write(
+            ours_path.join("pointer.txt"),
+            "version https://git-lfs.github.com/spec/v1\noid sha256:0123456789abcdef\nsize 1\n",
+        )?;
+
+        let edited_oid = format!("{:x}", Sha256::digest(&edited));
+
+        // status compares without keeping a copy
+        assert!(ours_repository.working_tree_status()?.is_dirty());
+
+        let local = FileSystemLfsStore::new(ours_path.join(".git/lfs/objects"));
+
+        assert!(!local.contains(&edited_oid, 4096)?);
+
+        ours_repository.commit()?;
+
+        assert!(local.contains(&edited_oid, 4096)?);
+
+        let uploaded =
+            upload_lfs_objects(&ours_repository, Some("origin"), &["refs/heads/main".to_string()])?;
+
+        assert!(uploaded == vec![edited_oid]);
+
+        // downloaded on pull
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        theirs_repository.pull(&origin, &PullOptions::default())?;
+
+        assert!(fs::read(theirs_path.join("photo.bin"))? == edited);
+
+        // uploaded on resolve
+        fs::write(theirs_path.join("scan.bin"), b"scan")?;
+
+        theirs_repository.commit()?;
+
+        assert!(theirs_repository.resolve(&origin)?.ok);
+
+        let scan = theirs_repository.repo.revparse_single("HEAD:scan.bin")?.peel_to_blob()?;
+
+        let (oid, size) = parse_pointer(scan.content()).unwrap();
+
+        assert!(store.download(&oid, size)? == b"scan");
+
+        // the remote is a path, lfs.url names the server
+        assert!(theirs_repository.http_lfs_store(&origin).is_err());
+
+        theirs_repository.repo.config()?.set_str("lfs.url", "https://lfs.example.com/name")?;
+
+        assert!(theirs_repository.http_lfs_store(&origin)?.url == "https://lfs.example.com/name");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use super::filters::smudge_content;
+use crate::{
+    tree_entry::{TreeEntry, TreeEntryKind},
+    Repository, Result,
+};
+use std::path::Path;
+
+// lists the tree at path, or the root tree if path is None
+pub fn list_tree(
+    repository: &Repository,
+    revision: &str,
+    path: Option<&str>,
+    recursive: bool,
+) -> Result<Vec<TreeEntry>> {
+    let root = repository.repo.revparse_single(revision)?.peel_to_tree()?;
+
+    let (tree, prefix) = match path {
+        Some(path) if !path.is_empty() => {
+            let tree = root
+                .get_path(Path::new(path))?
+                .to_object(&repository.repo)?
+                .peel_to_tree()?;
+
+            (tree, format!("{}/", path.trim_end_matches('/')))
+        }
+        _ => (root, String::new()),
+    };
+
+    let odb = repository.repo.odb()?;
+
+    let mut entries = vec![];
+
+    let mut error = None;
+
+    let mut push_entry = |parent: &str, entry: &git2::TreeEntry| {
+        let kind = match entry.kind() {
+            Some(git2::ObjectType::Tree) => TreeEntryKind::Tree,
+            Some(git2::ObjectType::Commit) => TreeEntryKind::Submodule,
+            _ => TreeEntryKind::Blob,
+        };
+
+        let path = format!("{}{}{}", prefix, parent, entry.name().unwrap_or_default());
+
+        let size = match kind {
+            TreeEntryKind::Blob => match blob_size(repository, &odb, &path, entry.id()) {
+                Ok(size) => Some(size),
+                Err(err) => {
+                    error = Some(err);
+                    return;
+                }
+            },
+            _ => None,
+        };
+
+        entries.push(TreeEntry {
+            path,
+            kind,
+            mode: entry.filemode(),
+            oid: entry.id().to_string(),
+            size,
+        });
+    };
+
+    if recursive {
+        tree.walk(git2::TreeWalkMode::PreOrder, |parent, entry| {
+            push_entry(parent, entry);
+            git2::TreeWalkResult::Ok
+        })?;
+    } else {
+        for entry in tree.iter() {
+            push_entry("", &entry);
+        }
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(entries),
+    }
+}
+
+// the size as checked out, filtered files are smudged to know it
+fn blob_size(repository: &Repository, odb: &git2::Odb, path: &str, oid: git2::Oid) -> Result<usize> {
+    if repository.filters.find(repository, path)?.is_none() {
+        return Ok(odb.read_header(oid)?.0);
+    }
+
+    let blob = repository.repo.find_blob(oid)?;
+
+    Ok(smudge_content(repository, path, blob.content())?.len())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result, TreeEntry, TreeEntryKind};
+    use std::fs::{create_dir_all, read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn list_tree_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        create_dir_all(temp_path.join("dir"))?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        File::create(temp_path.join("dir/bar.csv"))?.write_all(b"a,b\n")?;
+
+        let first = repository.commit()?;
+
+        repository.create_tag("v1", "HEAD", None)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"second!\n")?;
+
+        repository.commit()?;
+
+        // revisions resolve to commits without touching the working tree
+        assert!(repository.resolve_revision("HEAD~1")? == first);
+
+        assert!(repository.resolve_revision("v1")? == first);
+
+        assert!(repository.resolve_revision(&first.to_string()[..7])? == first);
+
+        assert!(repository.read_blob("v1", "foo.txt")? == b"first\n");
+
+        assert!(repository.read_blob("HEAD", "foo.txt")? == b"second!\n");
+
+        assert!(repository.read_blob("HEAD", "dir").is_err());
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "second!\n");
+
+        let entries = repository.list_tree("v1", None, false)?;
+
+        assert!(entries.len() == 2);
+
+        assert!(entries[0].path == "dir");
+
+        assert!(entries[0].kind == TreeEntryKind::Tree);
+
+        assert!(entries[0].size.is_none());
+
+        assert!(entries[1].path == "foo.txt");
+
+        assert!(entries[1].mode == 0o100644);
+
+        assert!(entries[1].size == Some(6));
+
+        let entries = repository.list_tree("HEAD", None, true)?;
+
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+
+        assert!(paths == vec!["dir", "dir/bar.csv", "foo.txt"]);
+
+        let entries = repository.list_tree("HEAD", Some("dir"), false)?;
+
+        assert!(entries.len() == 1);
+
+        assert!(entries[0].path == "dir/bar.csv");
+
+        assert!(entries[0].size == Some(4));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use super::filters::filtered_checkout;
+use super::merge_drivers::resolve_conflicts;
+use crate::{Repository, Result};
+
+// returns None and leaves the repository mid-merge if the index has conflicts
+pub fn merge(
+    repository: &Repository,
+    fetch_commit: git2::AnnotatedCommit,
+    message: &str,
+) -> Result<Option<git2::Oid>> {
+    filtered_checkout(repository, || {
+        Ok(repository.repo.merge(
+            &[&fetch_commit],
+            None,
+            Some(git2::build::CheckoutBuilder::new().safe()),
+        )?)
+    })?;
+
+    let mut index = repository.repo.index()?;
+
+    if index.has_conflicts() {
+        resolve_conflicts(repository, &mut index, true)?;
+
+        index.write()?;
+    }
+
+    if index.has_conflicts() {
+        log::debug!("merge: conflicts merging `{}`", fetch_commit.id());
+        return Ok(None);
+    }
+
+    let tree = repository.repo.find_tree(index.write_tree()?)?;
+
+    let signature = repository.signature()?;
+
+    let head_commit = repository.find_last_commit()?;
+
+    let their_commit = repository.repo.find_commit(fetch_commit.id())?;
+
+    let oid = repository.repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[&head_commit, &their_commit],
+    )?;
+
+    repository.repo.cleanup_state()?;
+
+    Ok(Some(oid))
+}
@@ -0,0 +1,326 @@
+use super::csv_merge::CsvMergeDriver;
+use super::filters::{clean, smudge_content};
+use super::registry::Registry;
+use crate::merge_driver::{MergeDriver, MergeOutcome};
+use crate::{Repository, ResolveConflict, Result};
+use std::path::Path;
+
+pub type MergeDrivers = Registry<dyn MergeDriver>;
+
+// the stage bits of an index entry's flags, non-zero for conflict entries
+pub const INDEX_STAGE_MASK: u16 = 0x3000;
+
+// csv files are merged by row unless another driver is registered
+impl Default for MergeDrivers {
+    fn default() -> Self {
+        let mut drivers = MergeDrivers::new("merge");
+
+        drivers.register("csv", Box::new(CsvMergeDriver::default()));
+
+        drivers.register_for("*.csv", Box::new(CsvMergeDriver::default()));
+
+        drivers
+    }
+}
+
+// runs the merge drivers over the conflicts in index and returns what is
+// left conflicted, merged files are also written to the working tree if
+// the index belongs to it, a driver that fails leaves a textual conflict
+pub fn resolve_conflicts(
+    repository: &Repository,
+    index: &mut git2::Index,
+    write_workdir: bool,
+) -> Result<Vec<ResolveConflict>> {
+    let entries = index
+        .conflicts()?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut conflicts = vec![];
+
+    for entry in entries {
+        let (our, their) = match (entry.our, entry.their) {
+            (Some(our), Some(their)) => (our, their),
+            // deleted on one side and changed on the other
+            (our, their) => {
+                let path = our.or(their).or(entry.ancestor).map(|entry| entry.path);
+                conflicts.push(ResolveConflict {
+                    path: String::from_utf8_lossy(&path.unwrap_or_default()).into_owned(),
+                    keys: vec![],
+                });
+                continue;
+            }
+        };
+
+        let path = String::from_utf8_lossy(&our.path).into_owned();
+
+        let driver = match repository.merge_drivers.find(repository, &path)? {
+            Some(driver) => driver,
+            None => {
+                conflicts.push(ResolveConflict { path, keys: vec![] });
+                continue;
+            }
+        };
+
+        // drivers merge working tree contents, like they are given in git
+        let merge = || -> Result<MergeOutcome> {
+            let smudged = |id| -> Result<Vec<u8>> {
+                smudge_content(repository, &path, repository.repo.find_blob(id)?.content())
+            };
+
+            let base = match &entry.ancestor {
+                Some(ancestor) => Some(smudged(ancestor.id)?),
+                None => None,
+            };
+
+            driver.merge(&path, base.as_deref(), &smudged(our.id)?, &smudged(their.id)?)
+        };
+
+        let outcome = match merge() {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                log::warn!("merge driver: failed to merge `{}`: {}", path, err);
+                MergeOutcome::Conflicted(vec![])
+            }
+        };
+
+        match outcome {
+            MergeOutcome::Merged(content) => {
+                log::debug!("merge driver: merged `{}`", path);
+
+                let stored = clean(repository, &path, &content)?;
+
+                let mut merged = our;
+
+                merged.id = repository.repo.blob(&stored)?;
+                merged.file_size = stored.len() as u32;
+                // clear the conflict stage bits so the entry is added resolved
+                merged.flags &= !INDEX_STAGE_MASK;
+
+                index.conflict_remove(Path::new(&path))?;
+                index.add(&merged)?;
+
+                if let (true, Some(workdir)) = (write_workdir, repository.repo.workdir()) {
+                    std::fs::write(workdir.join(&path), &content)?;
+                }
+            }
+            MergeOutcome::Conflicted(keys) => conflicts.push(ResolveConflict { path, keys }),
+        }
+    }
+
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Filter, MergeDriver, MergeOutcome, Origin, PullOptions, PullOutcome, PullStrategy,
+        PushOptions, Repository, Result,
+    };
+    use super::resolve_conflicts;
+    use super::super::test_support::diverge;
+    use std::fs;
+    use temp_dir::TempDir;
+
+    // keeps our lines and appends their lines we do not have
+    struct UnionDriver;
+
+    impl MergeDriver for UnionDriver {
+        fn merge(
+            &self,
+            _path: &str,
+            _base: Option<&[u8]>,
+            ours: &[u8],
+            theirs: &[u8],
+        ) -> Result<MergeOutcome> {
+            let ours = String::from_utf8_lossy(ours).into_owned();
+
+            let mut merged = ours.clone();
+
+            for line in String::from_utf8_lossy(theirs).lines() {
+                if !ours.lines().any(|our| our == line) {
+                    merged.push_str(&format!("{}\n", line));
+                }
+            }
+
+            Ok(MergeOutcome::Merged(merged.into_bytes()))
+        }
+    }
+
+    struct FailingDriver;
+
+    impl MergeDriver for FailingDriver {
+        fn merge(
+            &self,
+            _path: &str,
+            _base: Option<&[u8]>,
+            _ours: &[u8],
+            _theirs: &[u8],
+        ) -> Result<MergeOutcome> {
+            Err(crate::Error::from_message("cannot merge"))
+        }
+    }
+
+    // stores contents back to front, so lines only make sense smudged
+    struct ReverseFilter;
+
+    impl Filter for ReverseFilter {
+        fn clean(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            Ok(content.iter().rev().copied().collect())
+        }
+
+        fn smudge(&self, _path: &str, content: &[u8]) -> Result<Vec<u8>> {
+            Ok(content.iter().rev().copied().collect())
+        }
+    }
+
+    #[test]
+    fn merge_driver_filtered_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        repository.register_filter_for("*.log", ReverseFilter);
+
+        repository.register_merge_driver_for("*.log", UnionDriver);
+
+        let main = diverge(
+            &repository,
+            &[("notes.log", "base\n")],
+            &[("notes.log", "base\nmain\n")],
+            &[("notes.log", "base\ndev\n")],
+        )?;
+
+        let ours = repository.find_last_commit()?;
+
+        let theirs = repository.repo.find_commit(main)?;
+
+        let mut index = repository.repo.merge_commits(&ours, &theirs, None)?;
+
+        assert!(resolve_conflicts(&repository, &mut index, false)?.is_empty());
+
+        // the driver merged working tree contents, which are stored cleaned
+        let entry = index.get_path(std::path::Path::new("notes.log"), 0).unwrap();
+
+        let blob = repository.repo.find_blob(entry.id)?;
+
+        assert!(blob.content() == b"\nniam\nved\nesab");
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_driver_error_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut repository = Repository::init(&temp_path)?;
+
+        repository.register_merge_driver_for("*.fail", FailingDriver);
+
+        let main = diverge(
+            &repository,
+            &[("notes.fail", "base\n")],
+            &[("notes.fail", "base\nmain\n")],
+            &[("notes.fail", "base\ndev\n")],
+        )?;
+
+        let main = repository.repo.find_annotated_commit(main)?;
+
+        // the failure is left as a conflict instead of aborting the merge
+        assert!(repository.merge(main, "Merge branch 'main'")?.is_none());
+
+        assert!(repository.conflicts()? == vec!["notes.fail".to_string()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_driver_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(origin_path.to_str().unwrap(), None);
+
+        let ours_dir = TempDir::new();
+
+        let ours_path = ours_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut ours_repository = Repository::clone(ours_path.clone(), &origin)?;
+
+        fs::write(ours_path.join("notes.log"), "base\n")?;
+
+        ours_repository.commit()?;
+
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let mut theirs_repository = Repository::clone(theirs_path.clone(), &origin)?;
+
+        fs::write(ours_path.join("notes.log"), "base\nours\n")?;
+
+        ours_repository.commit()?;
+
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        fs::write(theirs_path.join("notes.log"), "base\ntheirs\n")?;
+
+        theirs_repository.commit()?;
+
+        // selected by the merge attribute
+        fs::write(theirs_path.join(".gitattributes"), "*.log merge=union\n")?;
+
+        theirs_repository.register_merge_driver("union", UnionDriver);
+
+        let options = PullOptions {
+            strategy: PullStrategy::Merge,
+            ..Default::default()
+        };
+
+        let outcome = theirs_repository.pull(&origin, &options)?;
+
+        assert!(outcome == PullOutcome::Merged("main".to_string()));
+
+        assert!(fs::read_to_string(theirs_path.join("notes.log"))? == "base\ntheirs\nours\n");
+
+        fs::write(
+            theirs_path.join("notes.log"),
+            "base\ntheirs\nours\ntheirs again\n",
+        )?;
+
+        theirs_repository.commit()?;
+
+        theirs_repository.push(&origin, &PushOptions::default())?;
+
+        // selected by pattern in resolve
+        ours_repository.register_merge_driver_for("*.log", UnionDriver);
+
+        fs::write(ours_path.join("notes.log"), "base\nours\nours again\n")?;
+
+        ours_repository.commit()?;
+
+        assert!(ours_repository.resolve(&origin)?.ok);
+
+        let notes = fs::read_to_string(ours_path.join("notes.log"))?;
+
+        assert!(notes == "base\nours\nours again\ntheirs\ntheirs again\n");
+
+        Ok(())
+    }
+}
@@ -1,41 +1,108 @@
+mod abort_operation;
 mod add;
+mod cached_default_branch;
+mod checkout_marker;
+mod checkout_tag;
+mod clean;
 mod clone;
 mod commit;
+mod commit_to_branch;
+mod conflicts;
 mod create_branch;
+mod create_tag;
 mod create_unborn;
+mod csv_diff;
+mod csv_merge;
+mod csv_table;
 mod default_branch_for_remote;
 mod default_remote;
+mod delete_tag;
+mod diff;
+mod encryption;
 mod fast_forward;
 mod fetch;
+mod filters;
 mod find_last_commit;
 mod head_branch;
 mod head_status;
 mod init;
 mod init_bare;
+mod lfs;
+mod list_tree;
+mod merge;
+mod merge_drivers;
 mod open;
 mod pull;
 mod push;
+mod read_blob;
+mod rebase;
+mod recover;
+mod reflog;
+mod refresh_remote_info;
+mod registry;
+mod reset;
 mod resolve;
+mod resolve_revision;
+mod restore;
+mod restore_branch;
+mod revert;
+mod signature;
+mod stash_apply;
+mod stash_drop;
+mod stash_handle;
+mod stash_pop;
+mod stash_save;
+mod stashes;
 mod status;
 mod switch;
 mod switch_branch;
+mod tags;
+#[cfg(test)]
+mod test_support;
+mod transaction;
+mod transaction_journal;
 mod try_default_branch;
 mod try_open;
+mod unshallow;
+mod unstage;
 mod upstream_status;
 mod working_tree_status;
 
-pub use pull::PullOutcome;
+pub use clone::CloneOptions;
+pub use commit_to_branch::CommitBuilder;
+pub use csv_diff::CsvDiffOptions;
+pub use csv_merge::CsvMergeDriver;
+pub use fetch::{FetchOptions, FetchOutcome, UpdatedRef};
+pub use pull::{PullOptions, PullOutcome, PullStrategy};
+pub use push::{PushOptions, PushOutcome};
+pub use recover::Recovery;
+pub use reset::ResetMode;
+pub use restore::RestoreSource;
+pub use transaction::Transaction;
 pub use upstream_status::UpstreamStatus;
 
 use super::{
-    head_status::HeadStatus, origin::Origin, repository_status::RepositoryStatus, resolve::Resolve,
-    settings::Settings, working_tree_status::WorkingTreeStatus,
+    csv_diff::CsvDiff, file_change::FileChange, filter::Filter, head_status::HeadStatus,
+    http_lfs_store::HttpLfsStore,
+    lfs_store::LfsStore, merge_driver::MergeDriver, origin::Origin, reflog_entry::ReflogEntry,
+    repository_state::RepositoryState,
+    repository_status::RepositoryStatus, resolve::Resolve,
+    settings::Settings, stash::{Stash, StashOutcome}, tag::Tag, tree_entry::TreeEntry,
+    working_tree_status::WorkingTreeStatus,
 };
 use crate::Result;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub struct Repository {
     repo: git2::Repository,
+    merge_drivers: merge_drivers::MergeDrivers,
+    filters: filters::Filters,
+    // set by enable_lfs, objects are uploaded here on push
+    lfs_store: Option<Arc<dyn LfsStore>>,
+    // set by configure, used by fetch, pull, push and resolve
+    settings: Settings,
 }
 
 impl Repository {
@@ -52,11 +119,38 @@ impl Repository {
     }
 
     pub fn clone(dataset_dir: PathBuf, origin: &Origin) -> Result<Self> {
-        clone::clone(dataset_dir, origin)
+        clone::clone(dataset_dir, origin, &CloneOptions::default())
+    }
+
+    // clones only the last depth commits of each branch
+    pub fn shallow_clone(dataset_dir: PathBuf, origin: &Origin, depth: u32) -> Result<Self> {
+        clone::clone(
+            dataset_dir,
+            origin,
+            &CloneOptions {
+                depth: Some(depth),
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn clone_with(dataset_dir: PathBuf, origin: &Origin, options: &CloneOptions) -> Result<Self> {
+        clone::clone(dataset_dir, origin, options)
     }
 
     pub fn status(&self, settings: &Settings) -> Result<(RepositoryStatus, Option<git2::Remote>)> {
-        status::status(self, settings)
+        status::status(self, settings, false)
+    }
+
+    pub fn offline_status(
+        &self,
+        settings: &Settings,
+    ) -> Result<(RepositoryStatus, Option<git2::Remote>)> {
+        status::status(self, settings, true)
+    }
+
+    pub fn refresh_remote_info(&self, settings: &Settings) -> Result<String> {
+        refresh_remote_info::refresh_remote_info(self, settings)
     }
 
     pub fn try_open(path: &Path) -> Result<Option<Self>> {
@@ -75,78 +169,172 @@ impl Repository {
         working_tree_status::working_tree_status(self)
     }
 
-    pub fn fetch(&self, origin: &Origin) -> Result<()> {
-        self.repo.remote_set_url("origin", &origin.url)?;
+    pub fn conflicts(&self) -> Result<Vec<String>> {
+        conflicts::conflicts(self)
+    }
 
-        let origin = self.find_remote("origin").unwrap();
+    pub fn fetch(&self, origin: &Origin, options: &FetchOptions) -> Result<FetchOutcome> {
+        let origin = self.origin_remote(origin)?;
 
-        fetch::fetch(self, origin);
+        fetch::fetch(self, &self.settings, origin, options)
+    }
 
-        Ok(())
+    // fetches the history a shallow clone or fetch left out
+    pub fn unshallow(&self, origin: &Origin) -> Result<FetchOutcome> {
+        let origin = self.origin_remote(origin)?;
+
+        unshallow::unshallow(self, &self.settings, origin)
     }
 
-    pub fn pull(&self, origin: &Origin) -> Result<pull::PullOutcome> {
+    pub fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    pub fn pull(&self, origin: &Origin, options: &PullOptions) -> Result<PullOutcome> {
+        // without a configured default branch the remote's is used
         let settings = Settings {
-            default_branch: Some("main".to_string()),
             default_remote: Some("origin".to_string()),
-            ssh: None,
-            editor: None,
-            ignore: None,
-            prune: None,
+            ..self.settings.clone()
         };
 
-        self.repo.remote_set_url("origin", &origin.url)?;
-
-        let origin = self.find_remote("origin").unwrap();
+        let origin = self.origin_remote(origin)?;
 
         let (status, remote) = self.status(&settings)?;
 
-        pull::pull(self, &settings, &status, Some(origin), true, |_| {})
+        pull::pull(self, &settings, &status, Some(origin), true, options, |_| {})
     }
 
     fn create_unborn(
         &self,
         status: &RepositoryStatus,
+        branch_name: &str,
+        remote_name: &str,
         fetch_commit: git2::AnnotatedCommit,
     ) -> Result<()> {
-        create_unborn::create_unborn(self, status, fetch_commit)
+        create_unborn::create_unborn(self, status, branch_name, remote_name, fetch_commit)
     }
 
     fn fast_forward(&self, fetch_commit: git2::AnnotatedCommit) -> Result<()> {
         fast_forward::fast_forward(self, fetch_commit)
     }
 
-    pub fn push(&self, origin: &Origin) -> Result<()> {
+    fn merge(&self, fetch_commit: git2::AnnotatedCommit, message: &str) -> Result<Option<git2::Oid>> {
+        merge::merge(self, fetch_commit, message)
+    }
+
+    fn rebase(&self, fetch_commit: git2::AnnotatedCommit) -> Result<Option<git2::Oid>> {
+        rebase::rebase(self, fetch_commit)
+    }
+
+    pub fn push(&self, origin: &Origin, options: &PushOptions) -> Result<Vec<PushOutcome>> {
         let settings = Settings {
-            default_branch: None,
-            default_remote: None,
-            ssh: None,
-            editor: None,
-            ignore: None,
-            prune: None,
+            default_remote: Some("origin".to_string()),
+            ..self.settings.clone()
         };
 
         self.repo.remote_set_url("origin", &origin.url)?;
 
         let (status, remote) = self.status(&settings)?;
 
-        push::push(self, &settings, &status, remote)
+        push::push(self, &settings, &status, remote, options)
     }
 
     pub fn resolve(&self, origin: &Origin) -> Result<Resolve> {
         resolve::resolve(self, origin)
     }
 
-    fn add(&self) -> Result<(git2::Oid, String)> {
-        add::add(self)
+    pub fn abort_operation(&self) -> Result<RepositoryState> {
+        abort_operation::abort_operation(self)
+    }
+
+    pub fn recover(&self, stale_after: Duration) -> Result<Recovery> {
+        recover::recover(self, stale_after)
+    }
+
+    pub fn stash_save(&self, message: Option<&str>, include_untracked: bool) -> Result<Option<git2::Oid>> {
+        stash_save::stash_save(self, message, include_untracked)
+    }
+
+    pub fn stashes(&self) -> Result<Vec<Stash>> {
+        stashes::stashes(self)
+    }
+
+    pub fn stash_apply(&self, index: usize) -> Result<StashOutcome> {
+        stash_apply::stash_apply(self, index)
+    }
+
+    pub fn stash_pop(&self, index: usize) -> Result<StashOutcome> {
+        stash_pop::stash_pop(self, index)
+    }
+
+    pub fn stash_drop(&self, index: usize) -> Result<()> {
+        stash_drop::stash_drop(self, index)
+    }
+
+    fn stash_handle(&self) -> Result<git2::Repository> {
+        stash_handle::stash_handle(self)
+    }
+
+    pub fn resolve_revision(&self, revision: &str) -> Result<git2::Oid> {
+        resolve_revision::resolve_revision(self, revision)
+    }
+
+    pub fn list_tree(
+        &self,
+        revision: &str,
+        path: Option<&str>,
+        recursive: bool,
+    ) -> Result<Vec<TreeEntry>> {
+        list_tree::list_tree(self, revision, path, recursive)
+    }
+
+    pub fn read_blob(&self, revision: &str, path: &str) -> Result<Vec<u8>> {
+        read_blob::read_blob(self, revision, path)
+    }
+
+    pub fn diff(&self, from: &str, to: &str) -> Result<Vec<FileChange>> {
+        diff::diff(self, from, to)
+    }
+
+    pub fn csv_diff(&self, from: &str, to: &str, options: &CsvDiffOptions) -> Result<Vec<CsvDiff>> {
+        csv_diff::csv_diff(self, from, to, options)
+    }
+
+    pub fn reflog(&self, reference: &str) -> Result<Vec<ReflogEntry>> {
+        reflog::reflog(self, reference)
+    }
+
+    pub fn restore_branch(&self, branch: &str, position: usize) -> Result<git2::Oid> {
+        restore_branch::restore_branch(self, branch, position)
+    }
+
+    pub fn restore(&self, paths: &[&str], source: &RestoreSource) -> Result<()> {
+        restore::restore(self, paths, source)
     }
 
-    fn create_branch(&self, settings: &Settings, name: &str) -> Result<()> {
-        create_branch::create_branch(self, settings, name)
+    pub fn unstage(&self, paths: &[&str]) -> Result<()> {
+        unstage::unstage(self, paths)
     }
 
-    fn switch_branch(&self, branch_name: &str) -> Result<()> {
-        switch_branch::switch_branch(self, branch_name)
+    pub fn clean(&self, dry_run: bool) -> Result<Vec<String>> {
+        clean::clean(self, dry_run)
+    }
+
+    fn add(&self, paths: &[&str]) -> Result<(git2::Oid, String)> {
+        add::add(self, paths)
+    }
+
+    pub fn create_branch(
+        &self,
+        settings: &Settings,
+        name: &str,
+        autostash: bool,
+    ) -> Result<Option<StashOutcome>> {
+        create_branch::create_branch(self, settings, name, autostash)
+    }
+
+    pub fn switch_branch(&self, branch_name: &str, autostash: bool) -> Result<Option<StashOutcome>> {
+        switch_branch::switch_branch(self, branch_name, autostash)
     }
 
     fn switch(&self, reference: &git2::Reference) -> Result<()> {
@@ -169,14 +357,112 @@ impl Repository {
         try_default_branch::try_default_branch(self, settings)
     }
 
+    fn cached_default_branch(
+        &self,
+        settings: &Settings,
+    ) -> (Option<String>, Option<git2::Remote>) {
+        cached_default_branch::cached_default_branch(self, settings)
+    }
+
     pub fn commit(&self) -> Result<git2::Oid> {
         commit::commit(self)
     }
 
+    fn commit_paths(&self, paths: &[&str]) -> Result<git2::Oid> {
+        commit::commit_paths(self, paths)
+    }
+
+    pub fn commit_to_branch(&self, builder: &CommitBuilder) -> Result<git2::Oid> {
+        commit_to_branch::commit_to_branch(self, builder)
+    }
+
+    // used for paths whose `merge` attribute in .gitattributes is name
+    pub fn register_merge_driver(&mut self, name: &str, driver: impl MergeDriver + 'static) {
+        self.merge_drivers.register(name, Box::new(driver))
+    }
+
+    // used for paths matching pattern without a registered `merge` attribute
+    pub fn register_merge_driver_for(
+        &mut self,
+        pattern: &str,
+        driver: impl MergeDriver + 'static,
+    ) {
+        self.merge_drivers.register_for(pattern, Box::new(driver))
+    }
+
+    // used for paths whose `filter` attribute in .gitattributes is name
+    pub fn register_filter(&mut self, name: &str, filter: impl Filter + 'static) {
+        self.filters.register(name, Box::new(filter))
+    }
+
+    // used for paths matching pattern without a registered `filter` attribute
+    pub fn register_filter_for(&mut self, pattern: &str, filter: impl Filter + 'static) {
+        self.filters.register_for(pattern, Box::new(filter))
+    }
+
+    // sets the settings used by fetch, pull, push and resolve and replaces
+    // the encryption keys, decrypting files the working tree has encrypted
+    pub fn configure(&mut self, settings: Settings) -> Result<()> {
+        encryption::configure_encryption(self, &settings)?;
+
+        self.settings = settings;
+
+        Ok(())
+    }
+
+    // stores files with the `filter=lfs` attribute through store, and
+    // downloads the objects of pointers already checked out
+    pub fn enable_lfs(&mut self, store: impl LfsStore + 'static) -> Result<()> {
+        lfs::enable_lfs(self, Arc::new(store))
+    }
+
+    // the LFS server of origin, or the one lfs.url configures
+    pub fn http_lfs_store(&self, origin: &Origin) -> Result<HttpLfsStore> {
+        lfs::http_lfs_store(self, origin)
+    }
+
+    // smudges files still in their stored form, such as after a clone made
+    // before the filters were registered
+    pub fn smudge(&self) -> Result<Vec<String>> {
+        filters::smudge(self)
+    }
+
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    pub fn reset(&self, revision: &str, mode: ResetMode, force: bool) -> Result<()> {
+        reset::reset(self, revision, mode, force)
+    }
+
+    pub fn revert(&self, revision: &str, mainline: Option<u32>) -> Result<Option<git2::Oid>> {
+        revert::revert(self, revision, mainline)
+    }
+
+    pub fn create_tag(&self, name: &str, revision: &str, message: Option<&str>) -> Result<git2::Oid> {
+        create_tag::create_tag(self, name, revision, message)
+    }
+
+    pub fn tags(&self) -> Result<Vec<Tag>> {
+        tags::tags(self)
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<()> {
+        delete_tag::delete_tag(self, name)
+    }
+
+    pub fn checkout_tag(&self, name: &str) -> Result<()> {
+        checkout_tag::checkout_tag(self, name)
+    }
+
     fn find_last_commit(&self) -> Result<git2::Commit> {
         find_last_commit::find_last_commit(self)
     }
 
+    fn signature(&self) -> Result<git2::Signature<'static>> {
+        signature::signature(self)
+    }
+
     fn find_remote(&self, remote: &str) -> Option<git2::Remote> {
         match self.repo.find_remote(remote) {
             Ok(r) => Some(r.into()),
@@ -220,4 +506,16 @@ impl Repository {
     fn remote(&self, name: &str, url: &str) -> Result<git2::Remote> {
         Ok(self.repo.remote(name, url)?)
     }
+
+    // creates the origin remote with default refspecs if it is missing
+    fn origin_remote(&self, origin: &Origin) -> Result<git2::Remote> {
+        match self.find_remote("origin") {
+            Some(_) => {
+                self.repo.remote_set_url("origin", &origin.url)?;
+
+                Ok(self.repo.find_remote("origin")?)
+            }
+            None => self.remote("origin", &origin.url),
+        }
+    }
 }
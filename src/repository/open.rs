@@ -6,5 +6,11 @@ pub fn open(path: &Path) -> Result<Repository> {
 
     log::debug!("opened repo at `{}`", path.display());
 
-    Ok(Repository { repo })
+    Ok(Repository {
+        repo,
+        merge_drivers: Default::default(),
+        filters: Default::default(),
+        lfs_store: None,
+        settings: Default::default(),
+    })
 }
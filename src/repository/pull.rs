@@ -1,9 +1,9 @@
 use crate::{
     credentials_state::CredentialsState, repository_status::RepositoryStatus, settings::Settings,
-    Repository, Result,
+    stash::StashOutcome, Repository, Result,
 };
-use super::fetch::fetch;
-use serde::Serialize;
+use super::fetch::{fetch, fetch_head, FetchOptions};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, PartialEq)]
 #[serde(tag = "state", content = "branch", rename_all = "snake_case")]
@@ -11,6 +11,45 @@ pub enum PullOutcome {
     UpToDate(String),
     CreatedUnborn(String),
     FastForwarded(String),
+    Merged(String),
+    Rebased(String),
+    // the merge, rebase or autostash stopped with conflicts in the working tree
+    Conflicted(String),
+}
+
+impl PullOutcome {
+    pub fn branch(&self) -> &str {
+        match self {
+            PullOutcome::UpToDate(branch)
+            | PullOutcome::CreatedUnborn(branch)
+            | PullOutcome::FastForwarded(branch)
+            | PullOutcome::Merged(branch)
+            | PullOutcome::Rebased(branch)
+            | PullOutcome::Conflicted(branch) => branch,
+        }
+    }
+}
+
+// how to reconcile local commits with the fetched branch when a fast-forward
+// is not possible
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStrategy {
+    #[default]
+    FastForwardOnly,
+    Merge,
+    Rebase,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PullOptions {
+    pub strategy: PullStrategy,
+    // stash local changes before pulling and re-apply them after
+    pub autostash: bool,
+    // overrides Settings.prune for this pull
+    pub prune: Option<bool>,
+    // number of commits to fetch from each tip, like FetchOptions.depth
+    pub depth: Option<u32>,
 }
 
 pub fn pull<F>(
@@ -19,43 +58,147 @@ pub fn pull<F>(
     status: &RepositoryStatus,
     remote: Option<git2::Remote>,
     switch: bool, // whether to switch to the default branch before pulling
+    options: &PullOptions,
     mut progress_callback: F,
 ) -> Result<PullOutcome>
 where
     F: FnMut(git2::Progress),
 {
+    // there is nothing to stash on top of before the first commit
+    let stashed = match options.autostash && !status.head.is_unborn() {
+        true => repository.stash_save(Some("git2kit: autostash"), true)?,
+        false => None,
+    };
+
+    let outcome = fetch_and_merge(repository, settings, status, remote, options);
+
+    if stashed.is_none() {
+        return outcome;
+    }
+
+    match outcome {
+        // the stash cannot be applied on top of a conflicted merge, keep it
+        Ok(PullOutcome::Conflicted(branch)) => Ok(PullOutcome::Conflicted(branch)),
+        Ok(outcome) => match repository.stash_pop(0)? {
+            StashOutcome::Applied => Ok(outcome),
+            StashOutcome::Conflicted(_) => Ok(PullOutcome::Conflicted(outcome.branch().to_owned())),
+        },
+        // the pull failed, so report that rather than a failure to re-apply
+        Err(err) => {
+            if let Err(pop_err) = repository.stash_pop(0) {
+                log::warn!("pull: failed to restore autostash: {}", pop_err);
+            }
+            Err(err)
+        }
+    }
+}
+
+fn fetch_and_merge(
+    repository: &Repository,
+    settings: &Settings,
+    status: &RepositoryStatus,
+    remote: Option<git2::Remote>,
+    options: &PullOptions,
+) -> Result<PullOutcome> {
     let remote = match remote {
         Some(remote) => remote,
         None => repository.default_remote(settings)?,
     };
 
-    let fetch_commit = fetch(repository, remote)?;
+    let remote_name = remote
+        .name()
+        .ok_or_else(|| crate::Error::from_message("remote name is invalid utf-8"))?
+        .to_owned();
+
+    let fetch_options = FetchOptions {
+        prune: options.prune,
+        depth: options.depth,
+        ..Default::default()
+    };
+
+    fetch(repository, settings, remote, &fetch_options)?;
+
+    let fetch_commit = fetch_head(repository)?;
+
+    // FETCH_HEAD cannot be trusted for an unborn head because no branch is
+    // tracked yet, so merge_analysis reports unborn against whichever ref
+    // the remote listed first. Check out the default branch explicitly.
+    if status.head.is_unborn() {
+        let branch_name = match &status.default_branch {
+            Some(name) => name.clone(),
+            None => remote_head(repository, &remote_name).unwrap_or_else(|| status.head.name.clone()),
+        };
+
+        let tracking_ref = repository
+            .repo
+            .find_reference(&format!("refs/remotes/{}/{}", remote_name, branch_name))?;
+
+        let fetch_commit = repository.repo.reference_to_annotated_commit(&tracking_ref)?;
+
+        log::debug!("pull: create unborn `{}` at `{}`", branch_name, fetch_commit.id());
+        repository.create_unborn(status, &branch_name, &remote_name, fetch_commit)?;
+        return Ok(PullOutcome::CreatedUnborn(branch_name));
+    }
 
     let (merge_analysis, _) = repository.repo.merge_analysis(&[&fetch_commit])?;
 
+    let branch_name = status.head.name.clone();
+
     if merge_analysis.is_up_to_date() {
         log::debug!("pull: up to date");
-        Ok(PullOutcome::UpToDate("main".to_owned()))
-    // comment unborn or it goes off all the time
-    //} else if merge_analysis.is_unborn() {
-    //    repository.create_unborn(status, fetch_commit)?;
-    //    Ok(PullOutcome::CreatedUnborn(default_branch))
+        Ok(PullOutcome::UpToDate(branch_name))
     } else if merge_analysis.is_fast_forward() {
         log::debug!("pull: fast forward `{}`", fetch_commit.id());
         repository.fast_forward(fetch_commit)?;
-        Ok(PullOutcome::FastForwarded("main".to_owned()))
+        Ok(PullOutcome::FastForwarded(branch_name))
     } else {
-        Err(crate::Error::from_message("cannot fast-forward"))
+        match options.strategy {
+            PullStrategy::FastForwardOnly => {
+                Err(crate::Error::from_message("cannot fast-forward"))
+            }
+            PullStrategy::Merge => {
+                log::debug!("pull: merge `{}`", fetch_commit.id());
+                let message = format!(
+                    "Merge remote-tracking branch '{}/{}'",
+                    remote_name, branch_name
+                );
+                match repository.merge(fetch_commit, &message)? {
+                    Some(_) => Ok(PullOutcome::Merged(branch_name)),
+                    None => Ok(PullOutcome::Conflicted(branch_name)),
+                }
+            }
+            PullStrategy::Rebase => {
+                log::debug!("pull: rebase onto `{}`", fetch_commit.id());
+                match repository.rebase(fetch_commit)? {
+                    Some(_) => Ok(PullOutcome::Rebased(branch_name)),
+                    None => Ok(PullOutcome::Conflicted(branch_name)),
+                }
+            }
+        }
     }
 }
 
+// the branch refs/remotes/<remote>/HEAD points at, recorded by clone
+fn remote_head(repository: &Repository, remote_name: &str) -> Option<String> {
+    let prefix = format!("refs/remotes/{}/", remote_name);
+
+    repository
+        .repo
+        .find_reference(&format!("{}HEAD", prefix))
+        .ok()?
+        .symbolic_target()?
+        .strip_prefix(&prefix)
+        .map(|name| name.to_owned())
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{Repository, Origin, Result, PullOutcome};
+    use crate::{Repository, Origin, Result, PullOptions, PullOutcome, PullStrategy};
     use temp_dir::TempDir;
     use std::fs::File;
     use std::io::prelude::*;
     use std::fs::read_dir;
+    use super::super::test_support::diverge;
 
     #[tokio::test]
     async fn pull_test() -> Result<()> {
@@ -88,10 +231,12 @@ mod test {
 
         let pull_repository = Repository::clone(pull_path.clone(), &pull_remote)?;
 
+        let branch_name = pull_repository.head_branch()?.name()?.unwrap().to_owned();
+
         // try to pull an up-to-date repository
-        let outcome = pull_repository.pull(&pull_remote)?;
+        let outcome = pull_repository.pull(&pull_remote, &PullOptions::default())?;
 
-        assert!(outcome == PullOutcome::UpToDate("main".to_string()));
+        assert!(outcome == PullOutcome::UpToDate(branch_name.clone()));
 
         let mut file = File::create(temp_path.join("foo.txt"))?;
 
@@ -100,9 +245,9 @@ mod test {
         temp_repository.commit()?;
 
         // try to pull a changed repository
-        let outcome = pull_repository.pull(&pull_remote)?;
+        let outcome = pull_repository.pull(&pull_remote, &PullOptions::default())?;
 
-        assert!(outcome == PullOutcome::FastForwarded("main".to_string()));
+        assert!(outcome == PullOutcome::FastForwarded(branch_name));
 
         // TODO check that merged foo.txt into pull_repository
         let foo = read_dir(&pull_path)?.find(|entry| {
@@ -113,4 +258,269 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn pull_unborn_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init(&origin_path)?;
+
+        let mut file = File::create(origin_path.join("foo.txt"))?;
+
+        file.write_all(b"Hello, world!")?;
+
+        // initial commit creates branch main
+        origin_repository.commit()?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let pull_dir = TempDir::new();
+
+        let pull_path = pull_dir.as_ref().unwrap().path().to_path_buf();
+
+        // a freshly initialized repository has no commits
+        let pull_repository = Repository::init(&pull_path)?;
+
+        let outcome = pull_repository.pull(&origin, &PullOptions::default())?;
+
+        assert!(outcome == PullOutcome::CreatedUnborn("main".to_string()));
+
+        let foo = read_dir(&pull_path)?.find(|entry| {
+            entry.as_ref().unwrap().file_name() == "foo.txt"
+        });
+
+        assert!(foo.is_some());
+
+        // the new branch tracks the remote branch
+        let head = pull_repository.head_branch()?;
+
+        assert!(head.name()? == Some("main"));
+
+        let upstream = head.upstream()?;
+
+        assert!(upstream.name()? == Some("origin/main"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pull_unborn_master_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init(&origin_path)?;
+
+        let mut file = File::create(origin_path.join("foo.txt"))?;
+
+        file.write_all(b"Hello, world!")?;
+
+        origin_repository.commit()?;
+
+        // the remote advertises master as its default branch
+        let git2_repository = git2::Repository::open(&origin_path)?;
+
+        git2_repository
+            .find_branch("main", git2::BranchType::Local)?
+            .rename("master", false)?;
+
+        git2_repository.set_head("refs/heads/master")?;
+
+        let origin = Origin::new(origin_path.to_str().unwrap(), None);
+
+        let pull_dir = TempDir::new();
+
+        let pull_path = pull_dir.as_ref().unwrap().path().to_path_buf();
+
+        let pull_repository = Repository::init(&pull_path)?;
+
+        let outcome = pull_repository.pull(&origin, &PullOptions::default())?;
+
+        assert!(outcome == PullOutcome::CreatedUnborn("master".to_string()));
+
+        assert!(pull_path.join("foo.txt").exists());
+
+        let head = pull_repository.head_branch()?;
+
+        assert!(head.name()? == Some("master"));
+
+        Ok(())
+    }
+
+    // publishes ours_file to a bare origin on top of a base commit and
+    // leaves a repository whose main adds theirs_file to the same base
+    fn diverge_from_origin(
+        origin_path: &std::path::Path,
+        theirs_path: &std::path::Path,
+        ours_file: &str,
+        theirs_file: &str,
+    ) -> Result<(Origin, Repository, git2::Oid)> {
+        let origin_repository = Repository::init_bare(origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let theirs_repository = Repository::init(theirs_path)?;
+
+        let ours = diverge(
+            &theirs_repository,
+            &[],
+            &[(ours_file, "ours\n")],
+            &[(theirs_file, "theirs\n")],
+        )?;
+
+        theirs_repository
+            .repo
+            .remote_anonymous(origin_path.to_str().unwrap())?
+            .push(&["refs/heads/main:refs/heads/main"], None)?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        // only the origin has the ours commit, the local main is dev
+        theirs_repository.repo.find_branch("main", git2::BranchType::Local)?.delete()?;
+
+        theirs_repository
+            .repo
+            .find_branch("dev", git2::BranchType::Local)?
+            .rename("main", false)?;
+
+        Ok((origin, theirs_repository, ours))
+    }
+
+    #[tokio::test]
+    async fn pull_merge_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let (origin, theirs_repository, _) =
+            diverge_from_origin(&origin_path, &theirs_path, "foo.txt", "bar.txt")?;
+
+        // fast-forward only refuses diverged histories
+        assert!(theirs_repository.pull(&origin, &PullOptions::default()).is_err());
+
+        let outcome = theirs_repository.pull(&origin, &PullOptions { strategy: PullStrategy::Merge, ..PullOptions::default() })?;
+
+        assert!(outcome == PullOutcome::Merged("main".to_string()));
+
+        let head = theirs_repository.find_last_commit()?;
+
+        assert!(head.parent_count() == 2);
+
+        assert!(theirs_path.join("foo.txt").exists());
+
+        assert!(theirs_path.join("bar.txt").exists());
+
+        assert!(theirs_repository.repo.state() == git2::RepositoryState::Clean);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pull_rebase_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let (origin, theirs_repository, ours) =
+            diverge_from_origin(&origin_path, &theirs_path, "foo.txt", "bar.txt")?;
+
+        let outcome = theirs_repository.pull(&origin, &PullOptions { strategy: PullStrategy::Rebase, ..PullOptions::default() })?;
+
+        assert!(outcome == PullOutcome::Rebased("main".to_string()));
+
+        // the local commit is replayed on top of the fetched one
+        let head = theirs_repository.find_last_commit()?;
+
+        assert!(head.parent_count() == 1);
+
+        assert!(head.parent_id(0)? == ours);
+
+        assert!(theirs_repository.head_branch()?.name()? == Some("main"));
+
+        assert!(theirs_path.join("foo.txt").exists());
+
+        assert!(theirs_path.join("bar.txt").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pull_conflict_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        // both sides write different contents to the same file
+        let (origin, theirs_repository, _) =
+            diverge_from_origin(&origin_path, &theirs_path, "foo.txt", "foo.txt")?;
+
+        let outcome = theirs_repository.pull(&origin, &PullOptions { strategy: PullStrategy::Merge, ..PullOptions::default() })?;
+
+        assert!(outcome == PullOutcome::Conflicted("main".to_string()));
+
+        assert!(theirs_repository.repo.state() == git2::RepositoryState::Merge);
+
+        assert!(theirs_repository.repo.index()?.has_conflicts());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pull_autostash_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let (origin, theirs_repository, _) =
+            diverge_from_origin(&origin_path, &theirs_path, "foo.txt", "bar.txt")?;
+
+        // an uncommitted edit is carried over the merge
+        File::create(theirs_path.join("bar.txt"))?.write_all(b"local\n")?;
+
+        let outcome = theirs_repository.pull(&origin, &PullOptions {
+            strategy: PullStrategy::Merge,
+            autostash: true,
+            ..PullOptions::default()
+        })?;
+
+        assert!(outcome == PullOutcome::Merged("main".to_string()));
+
+        assert!(theirs_path.join("foo.txt").exists());
+
+        assert!(std::fs::read_to_string(theirs_path.join("bar.txt"))? == "local\n");
+
+        assert!(theirs_repository.stashes()?.is_empty());
+
+        Ok(())
+    }
 }
@@ -1,23 +1,261 @@
-use crate::{repository_status::RepositoryStatus, settings::Settings, Repository, Result};
+use crate::{
+    credentials_state::CredentialsState, repository_status::RepositoryStatus, settings::Settings,
+    Repository, Result,
+};
+use super::head_status::{REFS_HEADS_NAMESPACE, REFS_TAGS_NAMESPACE};
+use super::lfs::upload_lfs_objects;
+use serde::Serialize;
+
+#[derive(Debug, Default, Clone)]
+pub struct PushOptions {
+    // overwrite the remote ref only if it still points at this commit
+    pub force_with_lease: Option<git2::Oid>,
+    // track the pushed branch like `git push -u`
+    pub set_upstream: bool,
+    // branches to push instead of the current one
+    pub branches: Vec<String>,
+    // push every local branch, overrides `branches`
+    pub all_branches: bool,
+    // push lightweight and annotated tags as well
+    pub tags: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PushOutcome {
+    Updated { reference: String },
+    UpToDate { reference: String },
+    Rejected { reference: String, message: String },
+}
 
 pub fn push(
     repository: &Repository,
     settings: &Settings,
     status: &RepositoryStatus,
     remote: Option<git2::Remote>,
-) -> Result<()> {
-    let mut remote = repository.find_remote("origin").unwrap();
+    options: &PushOptions,
+) -> Result<Vec<PushOutcome>> {
+    let mut remote = match remote {
+        Some(remote) => remote,
+        None => repository.default_remote(settings)?,
+    };
 
-    let head = repository.repo.head()?;
+    let mut references = branch_references(repository, options)?;
+
+    if options.tags {
+        for name in repository.repo.tag_names(None)?.iter().flatten() {
+            references.push(format!("{}{}", REFS_TAGS_NAMESPACE, name));
+        }
+    }
+
+    // the remote must have the LFS objects before the pointers
+    upload_lfs_objects(repository, remote.name(), &references)?;
+
+    let force = options.force_with_lease.is_some();
+
+    if force && references.len() != 1 {
+        return Err(crate::Error::from_message(
+            "force-with-lease needs exactly one ref to push",
+        ));
+    }
+
+    let mut refspecs: Vec<String> = references
+        .iter()
+        .map(|reference| match force {
+            true => format!("+{}:{}", reference, reference),
+            false => format!("{}:{}", reference, reference),
+        })
+        .collect();
+
+    let mut outcomes = vec![];
+
+    // refs rejected during negotiation abort the whole push,
+    // so push again without them until nothing is rejected
+    while !refspecs.is_empty() {
+        let mut up_to_date = vec![];
+        let mut rejected = vec![];
+        let mut reported = vec![];
+
+        let result = {
+            let mut callbacks = git2::RemoteCallbacks::new();
+            let mut credentials_state = CredentialsState::default();
+            callbacks.credentials(|url, username_from_url, allowed_types| {
+                credentials_state.get(
+                    settings,
+                    &git2::Config::open_default()?,
+                    url,
+                    username_from_url,
+                    allowed_types,
+                )
+            });
+            callbacks.push_negotiation(|updates| {
+                for update in updates {
+                    let reference = update.dst_refname().unwrap_or_default().to_owned();
+
+                    // src is what the remote has, dst is what we push
+                    if update.src() == update.dst() {
+                        up_to_date.push(reference);
+                    } else if let Some(message) =
+                        rejection(repository, options, &reference, update.src(), update.dst())
+                    {
+                        rejected.push((reference, message));
+                    }
+                }
+
+                match rejected.is_empty() {
+                    true => Ok(()),
+                    false => Err(git2::Error::from_str("push rejected")),
+                }
+            });
+            callbacks.push_update_reference(|reference, message| {
+                reported.push((reference.to_owned(), message.map(|m| m.to_owned())));
+                Ok(())
+            });
+
+            remote.push(
+                &refspecs,
+                Some(git2::PushOptions::new().remote_callbacks(callbacks)),
+            )
+        };
+
+        if rejected.is_empty() {
+            result?;
+
+            for (reference, message) in reported {
+                outcomes.push(match message {
+                    None if up_to_date.contains(&reference) => PushOutcome::UpToDate { reference },
+                    None => PushOutcome::Updated { reference },
+                    Some(message) => {
+                        log::debug!("push: server rejected `{}`: {}", reference, message);
+                        PushOutcome::Rejected { reference, message }
+                    }
+                });
+            }
+
+            break;
+        }
 
-    remote.push(&[head.name().unwrap()], None)?;
+        for (reference, message) in rejected {
+            log::debug!("push: rejected `{}`: {}", reference, message);
+
+            refspecs.retain(|refspec| !refspec.ends_with(&format!(":{}", reference)));
+
+            outcomes.push(PushOutcome::Rejected {
+                reference,
+                message: message.to_owned(),
+            });
+        }
+    }
+
+    if options.set_upstream {
+        let remote_name = remote
+            .name()
+            .ok_or_else(|| crate::Error::from_message("remote name is invalid utf-8"))?;
+
+        for outcome in &outcomes {
+            match outcome {
+                PushOutcome::Updated { reference } | PushOutcome::UpToDate { reference } => {
+                    set_upstream(repository, remote_name, reference)?
+                }
+                PushOutcome::Rejected { .. } => (),
+            }
+        }
+    }
+
+    Ok(outcomes)
+}
+
+fn set_upstream(repository: &Repository, remote_name: &str, reference: &str) -> Result<()> {
+    let branch_name = match reference.strip_prefix(REFS_HEADS_NAMESPACE) {
+        Some(branch_name) => branch_name,
+        None => return Ok(()),
+    };
+
+    let oid = repository.repo.refname_to_id(reference)?;
+
+    // the remote-tracking ref must exist before it can be set as upstream
+    repository.repo.reference(
+        &format!("refs/remotes/{}/{}", remote_name, branch_name),
+        oid,
+        true,
+        &format!("git2kit: push {} to {}", branch_name, remote_name),
+    )?;
+
+    let mut branch = repository
+        .repo
+        .find_branch(branch_name, git2::BranchType::Local)?;
+
+    branch.set_upstream(Some(&format!("{}/{}", remote_name, branch_name)))?;
+
+    log::debug!("push: set upstream of `{}` to `{}`", branch_name, remote_name);
 
     Ok(())
 }
 
+fn branch_references(repository: &Repository, options: &PushOptions) -> Result<Vec<String>> {
+    if options.all_branches {
+        let mut references = vec![];
+
+        for branch in repository.repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+
+            match branch.get().name() {
+                Some(name) => references.push(name.to_owned()),
+                None => {
+                    return Err(crate::Error::from_message(
+                        "branch name is invalid utf-8",
+                    ))
+                }
+            }
+        }
+
+        return Ok(references);
+    }
+
+    if !options.branches.is_empty() {
+        return Ok(options
+            .branches
+            .iter()
+            .map(|name| format!("{}{}", REFS_HEADS_NAMESPACE, name))
+            .collect());
+    }
+
+    let head = repository.repo.head()?;
+
+    match head.name() {
+        Some(name) => Ok(vec![name.to_owned()]),
+        None => Err(crate::Error::from_message("head name is invalid utf-8")),
+    }
+}
+
+fn rejection(
+    repository: &Repository,
+    options: &PushOptions,
+    reference: &str,
+    remote_oid: git2::Oid,
+    local_oid: git2::Oid,
+) -> Option<&'static str> {
+    match options.force_with_lease {
+        // the remote ref moved since the caller last saw it
+        Some(expected) if remote_oid != expected => Some("stale info"),
+        Some(_) => None,
+        // the remote ref does not exist yet
+        None if remote_oid.is_zero() => None,
+        // tags are never moved without force
+        None if reference.starts_with(REFS_TAGS_NAMESPACE) => Some("already exists"),
+        None => match repository.repo.graph_descendant_of(local_oid, remote_oid) {
+            Ok(true) => None,
+            Ok(false) => Some("non-fast-forward"),
+            // the remote commit is not present locally
+            Err(_) => Some("fetch first"),
+        },
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{Repository, Origin, Result};
+    use crate::{Repository, Origin, Result, PushOptions, PushOutcome, Settings};
+    use crate::repository::UpstreamStatus;
     use temp_dir::TempDir;
     use std::fs::File;
     use std::io::prelude::*;
@@ -56,7 +294,9 @@ mod test {
         push_repository.commit()?;
 
         // try to push an up-to-date repository
-        push_repository.push(&origin)?;
+        let outcomes = push_repository.push(&origin, &PushOptions::default())?;
+
+        assert!(outcomes == vec![PushOutcome::Updated { reference: "refs/heads/main".to_string() }]);
 
         origin_repository.repo.set_head("refs/heads/main")?;
 
@@ -74,6 +314,205 @@ mod test {
 
         assert!(foo.is_some());
 
+        // pushing again changes nothing
+        let outcomes = push_repository.push(&origin, &PushOptions::default())?;
+
+        assert!(outcomes == vec![PushOutcome::UpToDate { reference: "refs/heads/main".to_string() }]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn push_rejected_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let ours_dir = TempDir::new();
+
+        let ours_path = ours_dir.as_ref().unwrap().path().to_path_buf();
+
+        let ours_repository = Repository::clone(ours_path.clone(), &origin)?;
+
+        ours_repository.commit()?;
+
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        let theirs_dir = TempDir::new();
+
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_repository = Repository::clone(theirs_path.clone(), &origin)?;
+
+        let mut file = File::create(ours_path.join("foo.txt"))?;
+
+        file.write_all(b"ours\n")?;
+
+        let ours_oid = ours_repository.commit()?;
+
+        ours_repository.push(&origin, &PushOptions::default())?;
+
+        let mut file = File::create(theirs_path.join("bar.txt"))?;
+
+        file.write_all(b"theirs\n")?;
+
+        let theirs_oid = theirs_repository.commit()?;
+
+        // a diverged branch is not pushed
+        let outcomes = theirs_repository.push(&origin, &PushOptions::default())?;
+
+        assert!(outcomes == vec![PushOutcome::Rejected {
+            reference: "refs/heads/main".to_string(),
+            message: "fetch first".to_string(),
+        }]);
+
+        assert!(origin_repository.repo.refname_to_id("refs/heads/main")? == ours_oid);
+
+        // the lease does not match what the remote has
+        let outcomes = theirs_repository.push(&origin, &PushOptions {
+            force_with_lease: Some(theirs_oid),
+            ..PushOptions::default()
+        })?;
+
+        assert!(outcomes == vec![PushOutcome::Rejected {
+            reference: "refs/heads/main".to_string(),
+            message: "stale info".to_string(),
+        }]);
+
+        assert!(origin_repository.repo.refname_to_id("refs/heads/main")? == ours_oid);
+
+        // the lease matches, so the remote is overwritten
+        let outcomes = theirs_repository.push(&origin, &PushOptions {
+            force_with_lease: Some(ours_oid),
+            ..PushOptions::default()
+        })?;
+
+        assert!(outcomes == vec![PushOutcome::Updated { reference: "refs/heads/main".to_string() }]);
+
+        assert!(origin_repository.repo.refname_to_id("refs/heads/main")? == theirs_oid);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn push_upstream_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let push_dir = TempDir::new();
+
+        let push_path = push_dir.as_ref().unwrap().path().to_path_buf();
+
+        let push_repository = Repository::clone(push_path.clone(), &origin)?;
+
+        push_repository.commit()?;
+
+        // a new branch has no upstream
+        let (status, _) = push_repository.status(&Settings::default())?;
+
+        assert!(matches!(status.upstream, UpstreamStatus::None));
+
+        push_repository.push(&origin, &PushOptions {
+            set_upstream: true,
+            ..PushOptions::default()
+        })?;
+
+        let (status, _) = push_repository.status(&Settings::default())?;
+
+        assert!(matches!(status.upstream, UpstreamStatus::Upstream { ahead: 0, behind: 0 }));
+
+        let mut file = File::create(push_path.join("foo.txt"))?;
+
+        file.write_all(b"Hello, world!")?;
+
+        push_repository.commit()?;
+
+        let (status, _) = push_repository.status(&Settings::default())?;
+
+        assert!(matches!(status.upstream, UpstreamStatus::Upstream { ahead: 1, behind: 0 }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn push_refs_test() -> Result<()> {
+        let origin_dir = TempDir::new();
+
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let push_dir = TempDir::new();
+
+        let push_path = push_dir.as_ref().unwrap().path().to_path_buf();
+
+        let push_repository = Repository::clone(push_path.clone(), &origin)?;
+
+        let oid = push_repository.commit()?;
+
+        let commit = push_repository.repo.find_commit(oid)?;
+
+        push_repository.repo.branch("dev", &commit, false)?;
+
+        push_repository.repo.tag_lightweight("light", commit.as_object(), false)?;
+
+        let signature = git2::Signature::now("name", "name@mail.com")?;
+
+        let tag_oid = push_repository.repo.tag("v1", commit.as_object(), &signature, "v1", false)?;
+
+        // only the chosen branch is pushed
+        let outcomes = push_repository.push(&origin, &PushOptions {
+            branches: vec!["dev".to_string()],
+            ..PushOptions::default()
+        })?;
+
+        assert!(outcomes == vec![PushOutcome::Updated { reference: "refs/heads/dev".to_string() }]);
+
+        assert!(origin_repository.repo.find_reference("refs/heads/main").is_err());
+
+        let outcomes = push_repository.push(&origin, &PushOptions {
+            all_branches: true,
+            tags: true,
+            ..PushOptions::default()
+        })?;
+
+        assert!(outcomes.len() == 4);
+
+        assert!(outcomes.contains(&PushOutcome::UpToDate { reference: "refs/heads/dev".to_string() }));
+
+        assert!(outcomes.contains(&PushOutcome::Updated { reference: "refs/heads/main".to_string() }));
+
+        assert!(outcomes.contains(&PushOutcome::Updated { reference: "refs/tags/light".to_string() }));
+
+        assert!(outcomes.contains(&PushOutcome::Updated { reference: "refs/tags/v1".to_string() }));
+
+        // the annotated tag object reaches the remote
+        assert!(origin_repository.repo.refname_to_id("refs/tags/v1")? == tag_oid);
+
+        assert!(origin_repository.repo.find_tag(tag_oid)?.message() == Some("v1"));
+
         Ok(())
     }
 }
@@ -0,0 +1,17 @@
+use super::filters::smudge_content;
+use crate::{Repository, Result};
+use std::path::Path;
+
+// contents as checked out, smudged if a filter applies
+pub fn read_blob(repository: &Repository, revision: &str, path: &str) -> Result<Vec<u8>> {
+    let tree = repository.repo.revparse_single(revision)?.peel_to_tree()?;
+
+    let entry = tree.get_path(Path::new(path))?;
+
+    let blob = entry
+        .to_object(&repository.repo)?
+        .into_blob()
+        .map_err(|_| crate::Error::from_message(format!("`{}` is not a file", path)))?;
+
+    smudge_content(repository, path, blob.content())
+}
@@ -0,0 +1,57 @@
+use super::filters::filtered_checkout;
+use super::merge_drivers::resolve_conflicts;
+use crate::{Repository, Result};
+
+// returns None and leaves the rebase in progress if a commit does not apply
+pub fn rebase(
+    repository: &Repository,
+    fetch_commit: git2::AnnotatedCommit,
+) -> Result<Option<git2::Oid>> {
+    filtered_checkout(repository, || rebase_onto(repository, fetch_commit))
+}
+
+fn rebase_onto(
+    repository: &Repository,
+    fetch_commit: git2::AnnotatedCommit,
+) -> Result<Option<git2::Oid>> {
+    let signature = repository.signature()?;
+
+    let mut rebase = repository.repo.rebase(
+        None,
+        Some(&fetch_commit),
+        None,
+        Some(git2::RebaseOptions::new().checkout_options({
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.safe();
+            checkout
+        })),
+    )?;
+
+    while let Some(operation) = rebase.next() {
+        let operation = operation?;
+
+        let mut index = repository.repo.index()?;
+
+        if index.has_conflicts() {
+            resolve_conflicts(repository, &mut index, true)?;
+
+            index.write()?;
+        }
+
+        if index.has_conflicts() {
+            log::debug!("rebase: conflicts applying `{}`", operation.id());
+            return Ok(None);
+        }
+
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => (),
+            // the change is already upstream, skip it
+            Err(err) if err.code() == git2::ErrorCode::Applied => (),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    rebase.finish(Some(&signature))?;
+
+    Ok(Some(repository.find_last_commit()?.id()))
+}
@@ -0,0 +1,283 @@
+use super::checkout_marker::{end_checkout, read_checkout};
+use super::filters::{clean, filtered_checkout, smudge};
+use super::transaction_journal::rollback_transaction;
+use crate::{Repository, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Recovery {
+    // lock files removed, relative to the git directory
+    pub removed_locks: Vec<String>,
+    // the ref whose interrupted checkout was finished
+    pub completed_checkout: Option<String>,
+    // an interrupted transaction was rolled back
+    pub rolled_back_transaction: bool,
+    // filtered files an interrupted checkout left in their stored form
+    pub smudged_files: Vec<String>,
+}
+
+// the locks git2kit operations take besides those under refs/,
+// the rest of the git directory is left alone
+const LOCKS: [&str; 2] = ["index.lock", "HEAD.lock"];
+
+// locks younger than stale_after may belong to a running process and are kept
+pub fn recover(repository: &Repository, stale_after: Duration) -> Result<Recovery> {
+    let mut recovery = Recovery::default();
+
+    let git_dir = repository.repo.path().to_path_buf();
+
+    let mut kept_locks = vec![];
+
+    for name in LOCKS {
+        remove_stale_lock(
+            &git_dir,
+            &git_dir.join(name),
+            stale_after,
+            &mut recovery.removed_locks,
+            &mut kept_locks,
+        )?;
+    }
+
+    remove_stale_locks(
+        &git_dir,
+        &git_dir.join("refs"),
+        stale_after,
+        &mut recovery.removed_locks,
+        &mut kept_locks,
+    )?;
+
+    // another process may still be writing, leave the checkout to it
+    if !kept_locks.is_empty() {
+        log::debug!("recover: keeping fresh locks {:?}", kept_locks);
+        return Ok(recovery);
+    }
+
+    recovery.rolled_back_transaction = rollback_transaction(repository)?;
+
+    if let Some((reference, oid)) = read_checkout(repository)? {
+        complete_checkout(repository, &reference, oid)?;
+
+        end_checkout(repository)?;
+
+        log::debug!("recover: completed checkout of `{}` at `{}`", reference, oid);
+
+        recovery.completed_checkout = Some(reference);
+    }
+
+    recovery.smudged_files = smudge(repository)?;
+
+    Ok(recovery)
+}
+
+fn remove_stale_locks(
+    git_dir: &Path,
+    dir: &Path,
+    stale_after: Duration,
+    removed: &mut Vec<String>,
+    kept: &mut Vec<String>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        let path = entry.path();
+
+        if entry.metadata()?.is_dir() {
+            remove_stale_locks(git_dir, &path, stale_after, removed, kept)?;
+            continue;
+        }
+
+        if path.extension().map_or(false, |extension| extension == "lock") {
+            remove_stale_lock(git_dir, &path, stale_after, removed, kept)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn remove_stale_lock(
+    git_dir: &Path,
+    path: &Path,
+    stale_after: Duration,
+    removed: &mut Vec<String>,
+    kept: &mut Vec<String>,
+) -> Result<()> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let age = SystemTime::now()
+        .duration_since(metadata.modified()?)
+        .unwrap_or_default();
+
+    let relative = path.strip_prefix(git_dir).unwrap_or(path);
+
+    if age < stale_after {
+        kept.push(relative.to_string_lossy().into_owned());
+        return Ok(());
+    }
+
+    fs::remove_file(path)?;
+
+    log::debug!("recover: removed stale lock `{}`", relative.display());
+
+    removed.push(relative.to_string_lossy().into_owned());
+
+    Ok(())
+}
+
+// rolls the interrupted checkout forward, refusing to overwrite local edits
+fn complete_checkout(repository: &Repository, reference: &str, oid: git2::Oid) -> Result<()> {
+    let commit = repository.repo.find_commit(oid)?;
+
+    let target_tree = commit.tree()?;
+
+    // HEAD is unborn if the checkout was creating the first branch
+    let head_tree = repository
+        .repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .ok();
+
+    let diff = repository
+        .repo
+        .diff_tree_to_workdir_with_index(head_tree.as_ref(), None)?;
+
+    let workdir = repository
+        .repo
+        .workdir()
+        .ok_or_else(|| crate::Error::from_message("repository has no working tree"))?;
+
+    // every changed path must be either untouched or already checked out
+    for delta in diff.deltas() {
+        let path = match delta.new_file().path().or(delta.old_file().path()) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let file_path = workdir.join(path);
+
+        // compared in stored form, smudged files are not local edits
+        let workdir_oid = match file_path.is_file() {
+            true => {
+                let content = clean(repository, &path.to_string_lossy(), &fs::read(&file_path)?)?;
+                Some(git2::Oid::hash_object(git2::ObjectType::Blob, &content)?)
+            }
+            false => None,
+        };
+
+        let target_oid = target_tree.get_path(path).ok().map(|entry| entry.id());
+
+        let head_oid = head_tree
+            .as_ref()
+            .and_then(|tree| tree.get_path(path).ok())
+            .map(|entry| entry.id());
+
+        if workdir_oid != target_oid && workdir_oid != head_oid {
+            return Err(crate::Error::from_message(format!(
+                "local changes to `{}` conflict with the interrupted checkout",
+                path.display()
+            )));
+        }
+    }
+
+    filtered_checkout(repository, || {
+        repository.repo.checkout_tree(
+            commit.as_object(),
+            Some(git2::build::CheckoutBuilder::new().force()),
+        )?;
+
+        let mut target = repository.repo.find_reference(reference)?;
+
+        if target.target() != Some(oid) {
+            target.set_target(oid, "git2kit: recover interrupted checkout")?;
+        }
+
+        let head = repository.repo.find_reference("HEAD")?;
+
+        if head.symbolic_target() != Some(reference) {
+            repository.repo.set_head(reference)?;
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result};
+    use super::super::checkout_marker::begin_checkout;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn recover_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        let first = repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"Hello, world!")?;
+
+        let second = repository.commit()?;
+
+        // a crash left the tree checked out at second but the branch at first
+        repository.repo.reference("refs/heads/main", first, true, "test")?;
+
+        begin_checkout(&repository, "refs/heads/main", second)?;
+
+        File::create(repository.repo.path().join("index.lock"))?;
+
+        File::create(repository.repo.path().join("objects/pack/pack.lock"))?;
+
+        // a fresh lock may belong to a running process
+        let recovery = repository.recover(Duration::from_secs(600))?;
+
+        assert!(recovery == Default::default());
+
+        assert!(repository.repo.path().join("index.lock").exists());
+
+        let recovery = repository.recover(Duration::ZERO)?;
+
+        assert!(recovery.removed_locks == vec!["index.lock".to_string()]);
+
+        // locks outside index, HEAD and refs are not git2kit's
+        assert!(repository.repo.path().join("objects/pack/pack.lock").exists());
+
+        assert!(recovery.completed_checkout == Some("refs/heads/main".to_string()));
+
+        assert!(repository.find_last_commit()?.id() == second);
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        // nothing left to recover
+        let recovery = repository.recover(Duration::ZERO)?;
+
+        assert!(recovery == Default::default());
+
+        // local edits are not overwritten by recovery
+        repository.repo.reference("refs/heads/main", first, true, "test")?;
+
+        begin_checkout(&repository, "refs/heads/main", second)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"edited")?;
+
+        assert!(repository.recover(Duration::ZERO).is_err());
+
+        assert!(std::fs::read_to_string(temp_path.join("foo.txt"))? == "edited");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use super::head_status::{HEAD_FILE, REFS_HEADS_NAMESPACE};
+use crate::{reflog_entry::ReflogEntry, Repository, Result};
+
+// accepts HEAD, a full ref name or a local branch name
+pub fn reflog(repository: &Repository, reference: &str) -> Result<Vec<ReflogEntry>> {
+    let reflog = repository.repo.reflog(&reference_name(reference))?;
+
+    let entries = reflog
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| ReflogEntry {
+            index,
+            old: match entry.id_old().is_zero() {
+                true => None,
+                false => Some(entry.id_old().to_string()),
+            },
+            new: entry.id_new().to_string(),
+            message: entry.message().unwrap_or_default().to_owned(),
+            committer: entry.committer().name().unwrap_or_default().to_owned(),
+            time: entry.committer().when().seconds(),
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+pub fn reference_name(reference: &str) -> String {
+    if reference == HEAD_FILE || reference.starts_with("refs/") {
+        reference.to_owned()
+    } else {
+        format!("{}{}", REFS_HEADS_NAMESPACE, reference)
+    }
+}
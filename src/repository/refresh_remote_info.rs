@@ -0,0 +1,37 @@
+use crate::{credentials_state::CredentialsState, settings::Settings, Repository, Result};
+
+// connects to the default remote and caches its HEAD for offline status
+pub fn refresh_remote_info(repository: &Repository, settings: &Settings) -> Result<String> {
+    let mut remote = repository.default_remote(settings)?;
+
+    let mut callbacks = git2::RemoteCallbacks::new();
+    let mut credentials_state = CredentialsState::default();
+    callbacks.credentials(|url, username_from_url, allowed_types| {
+        credentials_state.get(
+            settings,
+            &git2::Config::open_default()?,
+            url,
+            username_from_url,
+            allowed_types,
+        )
+    });
+
+    let _ = remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None)?;
+
+    let default_branch = repository.default_branch_for_remote(&remote)?;
+
+    let remote_name = remote
+        .name()
+        .ok_or_else(|| crate::Error::from_message("remote name is invalid utf-8"))?;
+
+    repository.repo.reference_symbolic(
+        &format!("refs/remotes/{}/HEAD", remote_name),
+        &format!("refs/remotes/{}/{}", remote_name, default_branch),
+        true,
+        "git2kit: refresh remote HEAD",
+    )?;
+
+    log::debug!("cached default branch `{}` of `{}`", default_branch, remote_name);
+
+    Ok(default_branch)
+}
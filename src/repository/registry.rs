@@ -0,0 +1,70 @@
+use crate::{Repository, Result};
+use std::path::Path;
+
+// values selected by a .gitattributes attribute naming them, then by
+// path pattern, later registrations take precedence
+pub struct Registry<T: ?Sized> {
+    attribute: &'static str,
+    named: Vec<(String, Box<T>)>,
+    patterns: Vec<(String, Box<T>)>,
+    // set as a whole from settings, matched after the registered patterns
+    configured: Vec<(String, Box<T>)>,
+}
+
+impl<T: ?Sized> Registry<T> {
+    pub fn new(attribute: &'static str) -> Self {
+        Registry {
+            attribute,
+            named: vec![],
+            patterns: vec![],
+            configured: vec![],
+        }
+    }
+
+    pub fn register(&mut self, name: &str, value: Box<T>) {
+        self.named.push((name.to_string(), value));
+    }
+
+    pub fn register_for(&mut self, pattern: &str, value: Box<T>) {
+        self.patterns.push((pattern.to_string(), value));
+    }
+
+    // replaces the patterns set before, so configuring again does not stack
+    pub fn configure(&mut self, patterns: Vec<(String, Box<T>)>) {
+        self.configured = patterns;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.named.is_empty() && self.patterns.is_empty() && self.configured.is_empty()
+    }
+
+    pub fn find(&self, repository: &Repository, path: &str) -> Result<Option<&T>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let attribute = repository.repo.get_attr(
+            Path::new(path),
+            self.attribute,
+            git2::AttrCheckFlags::FILE_THEN_INDEX,
+        )?;
+
+        if let Some(name) = attribute {
+            if let Some((_, value)) = self.named.iter().rev().find(|(named, _)| named == name) {
+                return Ok(Some(value.as_ref()));
+            }
+        }
+
+        let patterns = self.patterns.iter().rev().chain(self.configured.iter().rev());
+
+        for (pattern, value) in patterns {
+            let pathspec = git2::Pathspec::new([pattern.as_str()])?;
+
+            if pathspec.matches_path(Path::new(path), git2::PathspecFlags::DEFAULT) {
+                return Ok(Some(value.as_ref()));
+            }
+        }
+
+        Ok(None)
+    }
+}
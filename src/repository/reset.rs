@@ -0,0 +1,140 @@
+use super::filters::filtered_checkout;
+use crate::{Repository, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetMode {
+    // moves the branch, keeps the index and working tree
+    Soft,
+    // moves the branch and resets the index, keeps the working tree
+    Mixed,
+    // moves the branch and discards all local changes
+    Hard,
+}
+
+// without force, refuses to leave commits reachable from no other ref
+pub fn reset(repository: &Repository, revision: &str, mode: ResetMode, force: bool) -> Result<()> {
+    let target = repository.repo.revparse_single(revision)?.peel_to_commit()?;
+
+    if !force {
+        let lost = unreferenced_commits(repository, target.id())?;
+
+        if lost > 0 {
+            return Err(crate::Error::from_message(format!(
+                "reset would lose {} unpushed commit(s)",
+                lost
+            )));
+        }
+    }
+
+    filtered_checkout(repository, || {
+        Ok(repository.repo.reset(
+            target.as_object(),
+            match mode {
+                ResetMode::Soft => git2::ResetType::Soft,
+                ResetMode::Mixed => git2::ResetType::Mixed,
+                ResetMode::Hard => git2::ResetType::Hard,
+            },
+            None,
+        )?)
+    })?;
+
+    log::debug!("reset {:?} to `{}`", mode, target.id());
+
+    Ok(())
+}
+
+// counts commits on HEAD that are not on the target, a remote or another ref
+fn unreferenced_commits(repository: &Repository, target: git2::Oid) -> Result<usize> {
+    let head = repository.repo.head()?;
+
+    let mut revwalk = repository.repo.revwalk()?;
+
+    revwalk.push(head.peel_to_commit()?.id())?;
+
+    revwalk.hide(target)?;
+
+    for reference in repository.repo.references()? {
+        let reference = reference?;
+
+        if reference.name() == head.name() || reference.symbolic_target().is_some() {
+            continue;
+        }
+
+        if let Ok(commit) = reference.peel_to_commit() {
+            revwalk.hide(commit.id())?;
+        }
+    }
+
+    Ok(revwalk.count())
+}
+
+#[cfg(test)]
+mod test {
+    use super::ResetMode;
+    use crate::{Origin, PushOptions, Repository, Result};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn reset_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(origin_path.to_str().unwrap(), None);
+
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::clone(temp_path.clone(), &origin)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        let first = repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"second\n")?;
+
+        let second = repository.commit()?;
+
+        repository.push(&origin, &PushOptions::default())?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"third\n")?;
+
+        repository.commit()?;
+
+        // the third commit only exists locally
+        assert!(repository.reset("HEAD~1", ResetMode::Mixed, false).is_err());
+
+        repository.reset("HEAD~1", ResetMode::Mixed, true)?;
+
+        assert!(repository.find_last_commit()?.id() == second);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "third\n");
+
+        assert!(repository.working_tree_status()?.working_changed);
+
+        // the second commit is on the remote, so it can be reset safely
+        repository.reset(&first.to_string(), ResetMode::Soft, false)?;
+
+        assert!(repository.find_last_commit()?.id() == first);
+
+        assert!(repository.working_tree_status()?.index_changed);
+
+        repository.reset("HEAD", ResetMode::Hard, false)?;
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "first\n");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        Ok(())
+    }
+}
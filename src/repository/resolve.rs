@@ -1,3 +1,6 @@
+use super::fetch::{prune_refspecs, should_prune, should_prune_tags};
+use super::lfs::upload_lfs_objects;
+use super::merge_drivers::resolve_conflicts;
 use crate::{Repository, Resolve, Result, Origin, Error};
 
 fn authorize(repository: &Repository, origin: &Origin) -> Vec<String>{
@@ -14,11 +17,21 @@ fn authorize(repository: &Repository, origin: &Origin) -> Vec<String>{
 fn fetch<'a>(repository: &'a Repository, headers: &Vec<&str>) -> Result<git2::AnnotatedCommit<'a>>{
     let remote = repository.find_remote("origin").ok_or(Error::from_message("Remote not found"))?;
 
-    remote.clone().fetch::<&str>(
-        &[],
+    let prune = should_prune(repository, &repository.settings, &remote, None)?;
+
+    let prune_tags = prune && should_prune_tags(repository, &repository.settings, &remote, None)?;
+
+    let refspecs = prune_refspecs(&remote, prune, prune_tags)?;
+
+    remote.clone().fetch(
+        &refspecs,
         Some(
             git2::FetchOptions::new()
                 .custom_headers(headers)
+                .prune(match prune {
+                    true => git2::FetchPrune::On,
+                    false => git2::FetchPrune::Off,
+                })
         ),
         Some("git2kit: fetching"),
     )?;
@@ -37,19 +50,47 @@ fn merge(repository: &Repository, fetch_commit: git2::AnnotatedCommit) -> Result
     if merge_analysis.is_up_to_date() {
         log::debug!("pull: up to date");
 
-        return Ok(Resolve { ok: true });
+        return Ok(Resolve { ok: true, conflicts: vec![] });
     } else if merge_analysis.is_fast_forward() {
         log::debug!("pull: fast forward `{}`", fetch_commit.id());
 
         repository.fast_forward(fetch_commit)?;
 
-        return Ok(Resolve { ok: true });
+        return Ok(Resolve { ok: true, conflicts: vec![] });
     } else {
-        // TODO diff3 resolve with resolution hunks
-        // TODO add
-        // TODO commit
-        // TODO return conflict hunks
-        return Ok(Resolve { ok: false });
+        let our_commit = repository.find_last_commit()?;
+
+        let their_commit = repository.repo.find_commit(fetch_commit.id())?;
+
+        let mut index = repository.repo.merge_commits(&our_commit, &their_commit, None)?;
+
+        let conflicts = resolve_conflicts(repository, &mut index, false)?;
+
+        if !conflicts.is_empty() {
+            log::debug!("resolve: conflicts merging `{}`", their_commit.id());
+
+            return Ok(Resolve { ok: false, conflicts });
+        }
+
+        let tree = repository.repo.find_tree(index.write_tree_to(&repository.repo)?)?;
+
+        let signature = repository.signature()?;
+
+        let oid = repository.repo.commit(
+            None,
+            &signature,
+            &signature,
+            &format!("git2kit: merge {}", their_commit.id()),
+            &tree,
+            &[&our_commit, &their_commit],
+        )?;
+
+        log::debug!("resolve: merged `{}` as `{}`", their_commit.id(), oid);
+
+        // ours is an ancestor of the merge, so move the branch like a fast-forward
+        repository.fast_forward(repository.repo.find_annotated_commit(oid)?)?;
+
+        return Ok(Resolve { ok: true, conflicts: vec![] });
     }
 }
 
@@ -58,6 +99,8 @@ fn push(repository: &Repository, headers: &Vec<&str>) -> Result<()> {
 
     let head = repository.repo.head()?;
 
+    upload_lfs_objects(repository, Some("origin"), &[head.name().unwrap().to_string()])?;
+
     remote.clone().push(&[head.name().unwrap()],
         Some(
             git2::PushOptions::new()
@@ -81,7 +124,9 @@ pub fn resolve(repository: &Repository, origin: &Origin) -> Result<Resolve> {
             let resolveResult = merge(repository, fetch_commit)?;
 
             // if merge succeeds, try to push
-            push(repository, &headers)?;
+            if resolveResult.ok {
+                push(repository, &headers)?;
+            }
 
             Ok(resolveResult)
         }
@@ -92,14 +137,14 @@ pub fn resolve(repository: &Repository, origin: &Origin) -> Result<Resolve> {
                 return Err(e);
             }
 
-            Ok(Resolve { ok: true })
+            Ok(Resolve { ok: true, conflicts: vec![] })
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{Repository, Origin, Result, Resolve};
+    use crate::{Repository, Origin, Result, Resolve, ResolveConflict};
     use temp_dir::TempDir;
     use std::fs::File;
     use std::io::prelude::*;
@@ -175,4 +220,85 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn resolve_csv_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let theirs_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let theirs_path = theirs_dir.as_ref().unwrap().path().to_path_buf();
+
+        let theirs_repository = Repository::init_bare(&theirs_path)?;
+
+        let origin = Origin::new(theirs_path.to_str().unwrap(), None);
+
+        let ours_dir = TempDir::new();
+
+        let ours_path = ours_dir.as_ref().unwrap().path().to_path_buf();
+
+        let ours_repository = Repository::clone(ours_path.clone(), &origin)?;
+
+        std::fs::write(ours_path.join("data.csv"), "id,name\n1,ada\n")?;
+
+        ours_repository.commit()?;
+
+        ours_repository.resolve(&origin)?;
+
+        theirs_repository.repo.set_head("refs/heads/main")?;
+
+        let other_dir = TempDir::new();
+
+        let other_path = other_dir.as_ref().unwrap().path().to_path_buf();
+
+        let other_repository = Repository::clone(other_path.clone(), &origin)?;
+
+        // both append a different row
+        std::fs::write(other_path.join("data.csv"), "id,name\n1,ada\n2,alan\n")?;
+
+        other_repository.commit()?;
+
+        assert!(other_repository.resolve(&origin)?.ok);
+
+        std::fs::write(ours_path.join("data.csv"), "id,name\n1,ada\n3,grace\n")?;
+
+        ours_repository.commit()?;
+
+        assert!(ours_repository.resolve(&origin)? == Resolve { ok: true, conflicts: vec![] });
+
+        assert!(read_to_string(ours_path.join("data.csv"))? == "id,name\n1,ada\n3,grace\n2,alan\n");
+
+        assert!(!ours_repository.working_tree_status()?.is_dirty());
+
+        other_repository.resolve(&origin)?;
+
+        assert!(read_to_string(other_path.join("data.csv"))? == "id,name\n1,ada\n3,grace\n2,alan\n");
+
+        // both change the same row
+        std::fs::write(other_path.join("data.csv"), "id,name\n1,countess\n3,grace\n2,alan\n")?;
+
+        other_repository.commit()?;
+
+        other_repository.resolve(&origin)?;
+
+        std::fs::write(ours_path.join("data.csv"), "id,name\n1,ada lovelace\n3,grace\n2,alan\n")?;
+
+        let head = ours_repository.commit()?;
+
+        let resolve = ours_repository.resolve(&origin)?;
+
+        assert!(!resolve.ok);
+
+        let conflict = ResolveConflict {
+            path: "data.csv".to_string(),
+            keys: vec!["1".to_string()],
+        };
+
+        assert!(resolve.conflicts == vec![conflict]);
+
+        assert!(ours_repository.find_last_commit()?.id() == head);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,8 @@
+use crate::{Repository, Result};
+
+// resolves revspecs such as `HEAD~3`, tag names or short oids to a commit
+pub fn resolve_revision(repository: &Repository, revision: &str) -> Result<git2::Oid> {
+    let object = repository.repo.revparse_single(revision)?;
+
+    Ok(object.peel_to_commit()?.id())
+}
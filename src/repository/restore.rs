@@ -0,0 +1,151 @@
+use super::filters::filtered_checkout;
+use crate::{Repository, Result};
+
+#[derive(Debug, Clone)]
+pub enum RestoreSource {
+    Head,
+    // keeps staged changes, only the working tree is restored
+    Index,
+    // any revspec, such as a branch, tag or oid
+    Revision(String),
+}
+
+// restores every path if none are given
+pub fn restore(repository: &Repository, paths: &[&str], source: &RestoreSource) -> Result<()> {
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    // paths are file names, not globs
+    checkout.force().disable_pathspec_match(true);
+
+    for path in paths {
+        checkout.path(path);
+    }
+
+    filtered_checkout(repository, || {
+        match source {
+            RestoreSource::Index => repository.repo.checkout_index(None, Some(&mut checkout))?,
+            RestoreSource::Head => repository.repo.checkout_head(Some(&mut checkout))?,
+            RestoreSource::Revision(revision) => {
+                let tree = repository.repo.revparse_single(revision)?.peel_to_tree()?;
+
+                // like `git restore --source`, the index is left alone
+                checkout.update_index(false);
+
+                repository
+                    .repo
+                    .checkout_tree(tree.as_object(), Some(&mut checkout))?
+            }
+        }
+
+        Ok(())
+    })?;
+
+    log::debug!("restored {:?} from {:?}", paths, source);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::RestoreSource;
+    use crate::{Repository, Result};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use std::path::Path;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn restore_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"first\n")?;
+
+        repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"second\n")?;
+
+        repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"edited\n")?;
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"edited\n")?;
+
+        // only the given path is restored
+        repository.restore(&["foo.txt"], &RestoreSource::Head)?;
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "second\n");
+
+        assert!(read_to_string(temp_path.join("bar.txt"))? == "edited\n");
+
+        repository.restore(&[], &RestoreSource::Revision("HEAD~1".to_string()))?;
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "first\n");
+
+        assert!(read_to_string(temp_path.join("bar.txt"))? == "first\n");
+
+        // the older contents are in the working tree only
+        assert!(!repository.working_tree_status()?.index_changed);
+
+        assert!(repository.working_tree_status()?.working_changed);
+
+        let mut index = repository.repo.index()?;
+
+        index.add_path(Path::new("foo.txt"))?;
+
+        index.write()?;
+
+        assert!(repository.working_tree_status()?.index_changed);
+
+        // unstaging keeps the working tree
+        repository.unstage(&["foo.txt"])?;
+
+        assert!(!repository.working_tree_status()?.index_changed);
+
+        assert!(repository.working_tree_status()?.working_changed);
+
+        // the index matches HEAD again, so restoring from it discards the edit
+        repository.restore(&[], &RestoreSource::Index)?;
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "second\n");
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn restore_literal_path_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("a.txt"))?.write_all(b"first\n")?;
+
+        File::create(temp_path.join("[ab].txt"))?.write_all(b"first\n")?;
+
+        repository.commit()?;
+
+        File::create(temp_path.join("a.txt"))?.write_all(b"edited\n")?;
+
+        File::create(temp_path.join("[ab].txt"))?.write_all(b"edited\n")?;
+
+        // the name is not read as a glob matching a.txt
+        repository.restore(&["[ab].txt"], &RestoreSource::Head)?;
+
+        assert!(read_to_string(temp_path.join("[ab].txt"))? == "first\n");
+
+        assert!(read_to_string(temp_path.join("a.txt"))? == "edited\n");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use super::checkout_marker::{abandon_checkout, begin_checkout, end_checkout};
+use super::filters::filtered_checkout;
+use super::reflog::reference_name;
+use crate::{Repository, Result};
+
+// moves a branch to where it was `position` reflog entries ago, which is
+// itself recorded in the reflog so the restore can be undone the same way
+pub fn restore_branch(repository: &Repository, branch: &str, position: usize) -> Result<git2::Oid> {
+    let name = reference_name(branch);
+
+    let reflog = repository.repo.reflog(&name)?;
+
+    let oid = match reflog.get(position) {
+        Some(entry) => entry.id_new(),
+        None => {
+            return Err(crate::Error::from_message(format!(
+                "`{}` has no reflog entry {}",
+                branch, position
+            )))
+        }
+    };
+
+    let mut reference = repository.repo.find_reference(&name)?;
+
+    let log_message = format!("git2kit: restore {} to {}@{{{}}}", branch, branch, position);
+
+    // a checked out branch takes the working tree with it
+    let is_head = repository
+        .repo
+        .find_reference("HEAD")?
+        .symbolic_target()
+        .is_some_and(|target| target == name);
+
+    if is_head {
+        let object = repository.repo.find_object(oid, None)?;
+        begin_checkout(repository, &name, oid)?;
+        filtered_checkout(repository, || {
+            repository
+                .repo
+                .checkout_tree(&object, Some(git2::build::CheckoutBuilder::new().safe()))
+                .map_err(|err| abandon_checkout(repository, err))?;
+
+            reference.set_target(oid, &log_message)?;
+
+            Ok(())
+        })?;
+        end_checkout(repository)?;
+    } else {
+        reference.set_target(oid, &log_message)?;
+    }
+
+    log::debug!("restored `{}` to `{}`", name, oid);
+
+    Ok(oid)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn restore_branch_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        let first = repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"second\n")?;
+
+        let second = repository.commit()?;
+
+        let reflog = repository.reflog("main")?;
+
+        assert!(reflog.len() == 2);
+
+        assert!(reflog[0].new == second.to_string());
+
+        assert!(reflog[0].old == Some(first.to_string()));
+
+        // the branch was created by the first commit
+        assert!(reflog[1].old.is_none());
+
+        assert!(repository.restore_branch("main", 1)? == first);
+
+        assert!(repository.find_last_commit()?.id() == first);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "first\n");
+
+        let reflog = repository.reflog("refs/heads/main")?;
+
+        assert!(reflog[0].message == "git2kit: restore main to main@{1}");
+
+        // the restore itself can be undone
+        assert!(repository.restore_branch("main", 1)? == second);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "second\n");
+
+        assert!(repository.restore_branch("main", 10).is_err());
+
+        // a local edit refuses the checkout and leaves nothing to recover
+        File::create(temp_path.join("foo.txt"))?.write_all(b"edited\n")?;
+
+        assert!(repository.restore_branch("main", 1).is_err());
+
+        assert!(repository.recover(Duration::ZERO)? == Default::default());
+
+        assert!(repository.find_last_commit()?.id() == second);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use super::filters::filtered_checkout;
+use crate::{Repository, Result};
+
+// mainline is the 1-based parent to keep when reverting a merge commit,
+// returns None and leaves the repository mid-revert if there are conflicts
+pub fn revert(
+    repository: &Repository,
+    revision: &str,
+    mainline: Option<u32>,
+) -> Result<Option<git2::Oid>> {
+    let commit = repository.repo.revparse_single(revision)?.peel_to_commit()?;
+
+    let mut options = git2::RevertOptions::new();
+
+    if let Some(mainline) = mainline {
+        options.mainline(mainline);
+    }
+
+    filtered_checkout(repository, || Ok(repository.repo.revert(&commit, Some(&mut options))?))?;
+
+    let mut index = repository.repo.index()?;
+
+    if index.has_conflicts() {
+        log::debug!("revert: conflicts reverting `{}`", commit.id());
+        return Ok(None);
+    }
+
+    let tree = repository.repo.find_tree(index.write_tree()?)?;
+
+    let signature = repository.signature()?;
+
+    let head_commit = repository.find_last_commit()?;
+
+    let message = format!(
+        "Revert \"{}\"\n\nThis reverts commit {}.\n",
+        commit.summary().unwrap_or_default(),
+        commit.id()
+    );
+
+    let oid = repository.repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit],
+    )?;
+
+    repository.repo.cleanup_state()?;
+
+    Ok(Some(oid))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn revert_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"second\n")?;
+
+        let second = repository.commit()?;
+
+        let oid = repository.revert("HEAD", None)?.unwrap();
+
+        let commit = repository.repo.find_commit(oid)?;
+
+        assert!(commit.parent_id(0)? == second);
+
+        assert!(commit.message().unwrap().starts_with("Revert \"foo.txt\""));
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "first\n");
+
+        assert!(repository.repo.state() == git2::RepositoryState::Clean);
+
+        Ok(())
+    }
+
+    #[test]
+    fn revert_merge_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
+
+        let oid = repository.commit()?;
+
+        repository.repo.branch("dev", &repository.repo.find_commit(oid)?, false)?;
+
+        File::create(temp_path.join("main.txt"))?.write_all(b"main\n")?;
+
+        repository.commit()?;
+
+        repository.switch_branch("dev", false)?;
+
+        File::create(temp_path.join("dev.txt"))?.write_all(b"dev\n")?;
+
+        repository.commit()?;
+
+        repository.switch_branch("main", false)?;
+
+        let dev = repository
+            .repo
+            .reference_to_annotated_commit(&repository.repo.find_reference("refs/heads/dev")?)?;
+
+        repository.merge(dev, "Merge branch 'dev'")?.unwrap();
+
+        assert!(temp_path.join("dev.txt").exists());
+
+        // a merge commit cannot be reverted without choosing a mainline
+        assert!(repository.revert("HEAD", None).is_err());
+
+        repository.revert("HEAD", Some(1))?.unwrap();
+
+        assert!(!temp_path.join("dev.txt").exists());
+
+        assert!(temp_path.join("main.txt").exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+use super::{Repository, Result};
+
+// falls back to the placeholder identity used by commit
+pub fn signature(repository: &Repository) -> Result<git2::Signature<'static>> {
+    match repository.repo.signature() {
+        Ok(signature) => Ok(signature),
+        Err(_) => Ok(git2::Signature::now("name", "name@mail.com")?),
+    }
+}
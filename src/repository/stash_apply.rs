@@ -0,0 +1,23 @@
+use super::filters::filtered_checkout;
+use crate::{stash::StashOutcome, Repository, Result};
+
+pub fn stash_apply(repository: &Repository, index: usize) -> Result<StashOutcome> {
+    let mut repo = repository.stash_handle()?;
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.safe().allow_conflicts(true).conflict_style_merge(true);
+
+    let mut options = git2::StashApplyOptions::new();
+    options.checkout_options(checkout);
+
+    filtered_checkout(repository, || Ok(repo.stash_apply(index, Some(&mut options))?))?;
+
+    let conflicts = repository.conflicts()?;
+
+    if conflicts.is_empty() {
+        Ok(StashOutcome::Applied)
+    } else {
+        log::debug!("stash: conflicts applying stash {}", index);
+        Ok(StashOutcome::Conflicted(conflicts))
+    }
+}
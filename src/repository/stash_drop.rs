@@ -0,0 +1,9 @@
+use crate::{Repository, Result};
+
+pub fn stash_drop(repository: &Repository, index: usize) -> Result<()> {
+    let mut repo = repository.stash_handle()?;
+
+    repo.stash_drop(index)?;
+
+    Ok(())
+}
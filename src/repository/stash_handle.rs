@@ -0,0 +1,7 @@
+use crate::{Repository, Result};
+
+// git2 needs a mutable repository for stash operations,
+// a second handle keeps the Repository API on &self
+pub fn stash_handle(repository: &Repository) -> Result<git2::Repository> {
+    Ok(git2::Repository::open(repository.repo.path())?)
+}
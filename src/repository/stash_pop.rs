@@ -0,0 +1,109 @@
+use crate::{stash::StashOutcome, Repository, Result};
+
+// like `git stash pop`, the stash is only dropped if it applied cleanly
+pub fn stash_pop(repository: &Repository, index: usize) -> Result<StashOutcome> {
+    let outcome = repository.stash_apply(index)?;
+
+    if outcome == StashOutcome::Applied {
+        repository.stash_drop(index)?;
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result, StashOutcome};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn stash_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
+
+        repository.commit()?;
+
+        // nothing to stash in a clean working tree
+        assert!(repository.stash_save(None, false)?.is_none());
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"first\n")?;
+
+        let first = repository.stash_save(Some("first"), false)?.unwrap();
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "base\n");
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"untracked\n")?;
+
+        // untracked files are only stashed on request
+        assert!(repository.stash_save(Some("second"), false)?.is_none());
+
+        repository.stash_save(Some("second"), true)?;
+
+        assert!(!temp_path.join("bar.txt").exists());
+
+        let stashes = repository.stashes()?;
+
+        assert!(stashes.len() == 2);
+
+        assert!(stashes[0].message.ends_with("second"));
+
+        assert!(stashes[1].oid == first.to_string());
+
+        // apply keeps the stash, pop drops it
+        assert!(repository.stash_apply(0)? == StashOutcome::Applied);
+
+        assert!(temp_path.join("bar.txt").exists());
+
+        assert!(repository.stashes()?.len() == 2);
+
+        repository.stash_drop(0)?;
+
+        assert!(repository.stash_pop(0)? == StashOutcome::Applied);
+
+        assert!(read_to_string(temp_path.join("foo.txt"))? == "first\n");
+
+        assert!(repository.stashes()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stash_conflict_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
+
+        repository.commit()?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"stashed\n")?;
+
+        repository.stash_save(None, false)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"committed\n")?;
+
+        repository.commit()?;
+
+        let outcome = repository.stash_pop(0)?;
+
+        assert!(outcome == StashOutcome::Conflicted(vec!["foo.txt".to_string()]));
+
+        // the stash is kept to retry after resolving
+        assert!(repository.stashes()?.len() == 1);
+
+        Ok(())
+    }
+}
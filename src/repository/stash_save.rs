@@ -0,0 +1,31 @@
+use super::filters::filtered_checkout;
+use crate::{Repository, Result};
+
+// returns None if there are no local changes to stash
+pub fn stash_save(
+    repository: &Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<Option<git2::Oid>> {
+    let mut repo = repository.stash_handle()?;
+
+    let signature = repository.signature()?;
+
+    let flags = match include_untracked {
+        true => git2::StashFlags::INCLUDE_UNTRACKED,
+        false => git2::StashFlags::DEFAULT,
+    };
+
+    let saved = filtered_checkout(repository, || {
+        Ok(repo.stash_save2(&signature, message, Some(flags)))
+    })?;
+
+    match saved {
+        Ok(oid) => {
+            log::debug!("stashed local changes as `{}`", oid);
+            Ok(Some(oid))
+        }
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
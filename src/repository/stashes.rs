@@ -0,0 +1,18 @@
+use crate::{stash::Stash, Repository, Result};
+
+pub fn stashes(repository: &Repository) -> Result<Vec<Stash>> {
+    let mut repo = repository.stash_handle()?;
+
+    let mut stashes = vec![];
+
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push(Stash {
+            index,
+            message: message.to_owned(),
+            oid: oid.to_string(),
+        });
+        true
+    })?;
+
+    Ok(stashes)
+}
@@ -3,12 +3,19 @@ use crate::{repository_status::RepositoryStatus, Repository, Result, Settings};
 pub fn status<'a>(
     repository: &'a Repository,
     settings: &Settings,
+    offline: bool, // whether to use cached remote info instead of connecting
 ) -> Result<(RepositoryStatus, Option<git2::Remote<'a>>)> {
     let head = repository.head_status()?;
     let upstream = repository.upstream_status(&head)?;
     let working_tree = repository.working_tree_status()?;
+    let state = repository.repo.state().into();
+    let conflicts = repository.conflicts()?;
+    let shallow = repository.repo.is_shallow();
 
-    let (default_branch, remote) = repository.try_default_branch(settings);
+    let (default_branch, remote) = match offline {
+        true => repository.cached_default_branch(settings),
+        false => repository.try_default_branch(settings),
+    };
 
     Ok((
         RepositoryStatus {
@@ -16,7 +23,134 @@ pub fn status<'a>(
             upstream,
             working_tree,
             default_branch,
+            state,
+            conflicts,
+            shallow,
         },
         remote,
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{PushOptions, Repository, RepositoryState, Origin, Result, Settings};
+    use std::fs::File;
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[tokio::test]
+    async fn offline_status_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let origin_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let origin_path = origin_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_repository = Repository::init_bare(&origin_path)?;
+
+        let origin = Origin::new(
+            origin_path.to_str().unwrap(),
+            None,
+        );
+
+        let push_dir = TempDir::new();
+
+        let push_path = push_dir.as_ref().unwrap().path().to_path_buf();
+
+        let push_repository = Repository::clone(push_path.clone(), &origin)?;
+
+        let oid = push_repository.commit()?;
+
+        let commit = push_repository.repo.find_commit(oid)?;
+
+        push_repository.repo.branch("dev", &commit, false)?;
+
+        push_repository.push(&origin, &PushOptions {
+            all_branches: true,
+            ..PushOptions::default()
+        })?;
+
+        origin_repository.repo.set_head("refs/heads/main")?;
+
+        // clone records the remote HEAD
+        let status_dir = TempDir::new();
+
+        let status_path = status_dir.as_ref().unwrap().path().to_path_buf();
+
+        let status_repository = Repository::clone(status_path.clone(), &origin)?;
+
+        let (status, _) = status_repository.offline_status(&Settings::default())?;
+
+        assert!(status.default_branch == Some("main".to_string()));
+
+        // the cache is stale until refreshed
+        origin_repository.repo.set_head("refs/heads/dev")?;
+
+        let (status, _) = status_repository.offline_status(&Settings::default())?;
+
+        assert!(status.default_branch == Some("main".to_string()));
+
+        assert!(status_repository.refresh_remote_info(&Settings::default())? == "dev");
+
+        let (status, _) = status_repository.offline_status(&Settings::default())?;
+
+        assert!(status.default_branch == Some("dev".to_string()));
+
+        // offline status works when the remote cannot be reached
+        status_repository.repo.remote_set_url("origin", "/nonexistent")?;
+
+        let (status, _) = status_repository.status(&Settings::default())?;
+
+        assert!(status.default_branch.is_none());
+
+        let (status, _) = status_repository.offline_status(&Settings::default())?;
+
+        assert!(status.default_branch == Some("dev".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn conflict_status_test() -> Result<()> {
+        let temp_dir = TempDir::new();
+
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
+
+        let oid = repository.commit()?;
+
+        repository.repo.branch("dev", &repository.repo.find_commit(oid)?, false)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"main\n")?;
+
+        repository.commit()?;
+
+        let main = repository.repo.reference_to_annotated_commit(&repository.repo.head()?)?;
+
+        repository.switch_branch("dev", false)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"dev\n")?;
+
+        repository.commit()?;
+
+        let (status, _) = repository.offline_status(&Settings::default())?;
+
+        assert!(status.state.is_clean());
+
+        assert!(!status.has_conflicts());
+
+        assert!(repository.merge(main, "Merge branch 'main'")?.is_none());
+
+        let (status, _) = repository.offline_status(&Settings::default())?;
+
+        assert!(status.state == RepositoryState::Merge);
+
+        assert!(status.conflicts == vec!["foo.txt".to_string()]);
+
+        Ok(())
+    }
+}
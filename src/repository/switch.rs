@@ -1,14 +1,24 @@
+use super::checkout_marker::{abandon_checkout, begin_checkout, end_checkout};
+use super::filters::filtered_checkout;
 use crate::{Repository, Result};
 
 pub fn switch(repository: &Repository, reference: &git2::Reference) -> Result<()> {
-    repository.repo.checkout_tree(
-        &reference.peel(git2::ObjectType::Tree)?,
-        Some(git2::build::CheckoutBuilder::new().safe()),
-    )?;
-
-    repository
-        .repo
-        .set_head(reference.name().expect("ref name is invalid utf-8"))?;
+    let name = reference.name().expect("ref name is invalid utf-8");
+
+    let tree = reference.peel(git2::ObjectType::Tree)?;
+
+    begin_checkout(repository, name, reference.peel_to_commit()?.id())?;
+
+    filtered_checkout(repository, || {
+        repository
+            .repo
+            .checkout_tree(&tree, Some(git2::build::CheckoutBuilder::new().safe()))
+            .map_err(|err| abandon_checkout(repository, err))?;
+
+        Ok(repository.repo.set_head(name)?)
+    })?;
+
+    end_checkout(repository)?;
 
     Ok(())
 }
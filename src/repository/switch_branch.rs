@@ -1,12 +1,92 @@
 use super::{Repository, Result};
+use crate::stash::StashOutcome;
 
-pub fn switch_branch(repository: &Repository, branch_name: &str) -> Result<()> {
+// with autostash, local changes are carried over to the branch
+pub fn switch_branch(
+    repository: &Repository,
+    branch_name: &str,
+    autostash: bool,
+) -> Result<Option<StashOutcome>> {
     let reference = repository
         .repo
         .find_branch(branch_name, git2::BranchType::Local)?
         .into_reference();
 
-    repository.switch(&reference)?;
+    let stashed = match autostash {
+        true => repository.stash_save(Some("git2kit: autostash"), true)?,
+        false => None,
+    };
 
-    Ok(())
+    if let Err(err) = repository.switch(&reference) {
+        // put the changes back where they were
+        if stashed.is_some() {
+            if let Err(pop_err) = repository.stash_pop(0) {
+                log::warn!("switch_branch: failed to restore autostash: {}", pop_err);
+            }
+        }
+        return Err(err);
+    }
+
+    match stashed {
+        Some(_) => Ok(Some(repository.stash_pop(0)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result, StashOutcome};
+    use std::fs::{read_to_string, File};
+    use std::io::prelude::*;
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn switch_branch_autostash_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"base\n")?;
+
+        let oid = repository.commit()?;
+
+        repository.repo.branch("dev", &repository.repo.find_commit(oid)?, false)?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"main\n")?;
+
+        repository.commit()?;
+
+        // nothing to carry over
+        assert!(repository.switch_branch("dev", true)?.is_none());
+
+        File::create(temp_path.join("bar.txt"))?.write_all(b"local\n")?;
+
+        File::create(temp_path.join("foo.txt"))?.write_all(b"local\n")?;
+
+        // the local edit conflicts with main, so a plain switch refuses
+        assert!(repository.switch_branch("main", false).is_err());
+
+        // the refused switch left nothing to recover
+        assert!(repository.recover(Duration::ZERO)? == Default::default());
+
+        assert!(repository.head_branch()?.name()? == Some("dev"));
+
+        let outcome = repository.switch_branch("main", true)?;
+
+        assert!(outcome == Some(StashOutcome::Conflicted(vec!["foo.txt".to_string()])));
+
+        assert!(repository.head_branch()?.name()? == Some("main"));
+
+        assert!(read_to_string(temp_path.join("bar.txt"))? == "local\n");
+
+        assert!(repository.stashes()?.len() == 1);
+
+        Ok(())
+    }
 }
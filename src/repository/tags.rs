@@ -0,0 +1,119 @@
+use super::head_status::REFS_TAGS_NAMESPACE;
+use crate::{tag::Tag, Repository, Result};
+
+pub fn tags(repository: &Repository) -> Result<Vec<Tag>> {
+    let mut tags = vec![];
+
+    for name in repository.repo.tag_names(None)?.iter().flatten() {
+        let reference = repository
+            .repo
+            .find_reference(&format!("{}{}", REFS_TAGS_NAMESPACE, name))?;
+
+        // peel through annotated tags, tags may also point at a tree or blob
+        let target = reference.peel(git2::ObjectType::Any)?.id().to_string();
+
+        // lightweight tags point straight at the commit
+        let message = match reference.peel_to_tag() {
+            Ok(tag) => Some(tag.message().unwrap_or_default().to_owned()),
+            Err(_) => None,
+        };
+
+        tags.push(Tag {
+            name: name.to_owned(),
+            target,
+            message,
+        });
+    }
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Repository, Result, Tag};
+    use std::fs::File;
+    use std::io::prelude::*;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn tags_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        let mut file = File::create(temp_path.join("foo.txt"))?;
+
+        file.write_all(b"Hello, world!")?;
+
+        let first = repository.commit()?;
+
+        repository.create_tag("v1", "HEAD", Some("first release"))?;
+
+        repository.create_tag("light", "HEAD", None)?;
+
+        file.write_all(b"foobar!")?;
+
+        let second = repository.commit()?;
+
+        repository.create_tag("v2", "HEAD", Some("second release"))?;
+
+        let tags = repository.tags()?;
+
+        assert!(tags == vec![
+            Tag {
+                name: "light".to_string(),
+                target: first.to_string(),
+                message: None,
+            },
+            Tag {
+                name: "v1".to_string(),
+                target: first.to_string(),
+                message: Some("first release".to_string()),
+            },
+            Tag {
+                name: "v2".to_string(),
+                target: second.to_string(),
+                message: Some("second release".to_string()),
+            },
+        ]);
+
+        repository.delete_tag("light")?;
+
+        assert!(repository.tags()?.len() == 2);
+
+        // a tag on a blob is listed with the blob as its target
+        let git2_repository = git2::Repository::open(&temp_path)?;
+
+        let blob = git2_repository.blob(b"not a commit")?;
+
+        git2_repository.tag_lightweight("blob", &git2_repository.find_object(blob, None)?, false)?;
+
+        let tags = repository.tags()?;
+
+        assert!(tags.iter().any(|tag| tag.name == "blob" && tag.target == blob.to_string()));
+
+        repository.delete_tag("blob")?;
+
+        // checking out a tag detaches HEAD at the tagged commit
+        repository.checkout_tag("v1")?;
+
+        let head = repository.head_status()?;
+
+        assert!(head.is_detached());
+
+        assert!(head.name == "v1");
+
+        assert!(repository.find_last_commit()?.id() == first);
+
+        let contents = std::fs::read_to_string(temp_path.join("foo.txt"))?;
+
+        assert!(contents == "Hello, world!");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{Repository, Result};
+use std::fs;
+
+// commits `base`, then `main` on main and `dev` on a dev branch started
+// from the base commit, leaving dev checked out; returns the main commit
+pub fn diverge(
+    repository: &Repository,
+    base: &[(&str, &str)],
+    main: &[(&str, &str)],
+    dev: &[(&str, &str)],
+) -> Result<git2::Oid> {
+    let workdir = repository.repo.workdir().expect("repository has no working tree");
+
+    for (path, content) in base {
+        fs::write(workdir.join(path), content)?;
+    }
+
+    let oid = repository.commit()?;
+
+    repository.repo.branch("dev", &repository.repo.find_commit(oid)?, false)?;
+
+    for (path, content) in main {
+        fs::write(workdir.join(path), content)?;
+    }
+
+    let main = repository.commit()?;
+
+    repository.switch_branch("dev", false)?;
+
+    for (path, content) in dev {
+        fs::write(workdir.join(path), content)?;
+    }
+
+    repository.commit()?;
+
+    Ok(main)
+}
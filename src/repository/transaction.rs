@@ -0,0 +1,291 @@
+use super::commit_to_branch::validate_path;
+use super::transaction_journal::{begin_transaction, end_transaction, rollback_transaction};
+use crate::{Repository, Result};
+use std::collections::BTreeMap;
+use std::fs;
+
+// file writes staged in memory and committed together, on error the
+// working tree and index are rolled back to where they were
+pub struct Transaction<'a> {
+    repository: &'a Repository,
+    // None deletes the path
+    changes: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(repository: &'a Repository) -> Self {
+        Transaction {
+            repository,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    pub fn write(&mut self, path: &str, content: impl Into<Vec<u8>>) -> &mut Self {
+        self.changes.insert(path.to_string(), Some(content.into()));
+        self
+    }
+
+    pub fn delete(&mut self, path: &str) -> &mut Self {
+        self.changes.insert(path.to_string(), None);
+        self
+    }
+
+    pub fn commit(&self) -> Result<git2::Oid> {
+        let paths: Vec<&str> = self.changes.keys().map(|path| path.as_str()).collect();
+
+        for path in &paths {
+            validate_path(path)?;
+        }
+
+        begin_transaction(self.repository, &paths)?;
+
+        // unrelated changes in the working tree are left out of the commit
+        match self.apply().and_then(|_| self.repository.commit_paths(&paths)) {
+            Ok(oid) => {
+                end_transaction(self.repository)?;
+                Ok(oid)
+            }
+            Err(err) => {
+                log::debug!("transaction failed: {}", err);
+                rollback_transaction(self.repository)?;
+                Err(err)
+            }
+        }
+    }
+
+    fn apply(&self) -> Result<()> {
+        let workdir = self
+            .repository
+            .repo
+            .workdir()
+            .ok_or_else(|| crate::Error::from_message("repository has no working tree"))?;
+
+        for (path, content) in &self.changes {
+            let file_path = workdir.join(path);
+
+            match content {
+                Some(content) => {
+                    if let Some(parent) = file_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&file_path, content)?;
+                }
+                None => {
+                    if file_path.exists() {
+                        fs::remove_file(&file_path)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::transaction_journal::begin_transaction;
+    use crate::{Repository, Result};
+    use std::fs;
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn transaction_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(temp_path.join("a.txt"), "a")?;
+
+        fs::write(temp_path.join("old.txt"), "old")?;
+
+        fs::write(temp_path.join("unrelated.txt"), "unrelated")?;
+
+        let first = repository.commit()?;
+
+        // an edit outside the transaction
+        fs::write(temp_path.join("unrelated.txt"), "edited")?;
+
+        let mut transaction = repository.transaction();
+
+        transaction
+            .write("a.txt", "changed")
+            .write("docs/b.txt", "b")
+            .delete("old.txt");
+
+        let second = transaction.commit()?;
+
+        let commit = repository.repo.find_commit(second)?;
+
+        assert!(commit.parent_id(0)? == first);
+
+        let tree = commit.tree()?;
+
+        assert!(tree.get_path(std::path::Path::new("docs/b.txt")).is_ok());
+
+        assert!(tree.get_path(std::path::Path::new("old.txt")).is_err());
+
+        // the edit is neither committed nor staged
+        assert!(repository.read_blob(&second.to_string(), "unrelated.txt")? == b"unrelated");
+
+        let status = repository.working_tree_status()?;
+
+        assert!(!status.index_changed);
+
+        assert!(status.working_changed);
+
+        // paths may not leave the working tree
+        assert!(repository.transaction().write("../escape.txt", "x").commit().is_err());
+
+        assert!(repository.transaction().write("/tmp/escape.txt", "x").commit().is_err());
+
+        assert!(!temp_path.parent().unwrap().join("escape.txt").exists());
+
+        assert!(repository.find_last_commit()?.id() == second);
+
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_staged_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(temp_path.join("staged.txt"), "staged")?;
+
+        repository.commit()?;
+
+        // staged before the transaction, like `git add`
+        fs::write(temp_path.join("staged.txt"), "edited")?;
+
+        fs::write(temp_path.join("new.txt"), "new")?;
+
+        repository.add(&["staged.txt", "new.txt"])?;
+
+        let oid = repository.transaction().write("a.txt", "a").commit()?;
+
+        let tree = repository.repo.find_commit(oid)?.tree()?;
+
+        assert!(tree.get_path(std::path::Path::new("a.txt")).is_ok());
+
+        assert!(tree.get_path(std::path::Path::new("new.txt")).is_err());
+
+        assert!(repository.read_blob(&oid.to_string(), "staged.txt")? == b"staged");
+
+        // the staged changes are still staged
+        assert!(repository.working_tree_status()?.index_changed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rollback_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(temp_path.join("a.txt"), "a")?;
+
+        fs::create_dir(temp_path.join("dir"))?;
+
+        fs::write(temp_path.join("dir").join("c.txt"), "c")?;
+
+        let head = repository.commit()?;
+
+        let mut transaction = repository.transaction();
+
+        // a.txt is written before writing over the directory fails
+        transaction
+            .write("a.txt", "changed")
+            .write("b.txt", "b")
+            .write("dir", "not a directory");
+
+        assert!(transaction.commit().is_err());
+
+        assert!(fs::read_to_string(temp_path.join("a.txt"))? == "a");
+
+        assert!(!temp_path.join("b.txt").exists());
+
+        assert!(repository.find_last_commit()?.id() == head);
+
+        assert!(!repository.working_tree_status()?.is_dirty());
+
+        assert!(!repository.repo.path().join("GIT2KIT_TRANSACTION").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn recover_transaction_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let temp_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let temp_path = temp_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::init(&temp_path)?;
+
+        fs::write(temp_path.join("a.txt"), "a")?;
+
+        let head = repository.commit()?;
+
+        // a crash after the first write left the journal behind
+        begin_transaction(&repository, &["a.txt", "b.txt"])?;
+
+        fs::write(temp_path.join("a.txt"), "changed")?;
+
+        fs::write(temp_path.join("b.txt"), "b")?;
+
+        // a second transaction waits for recovery
+        assert!(
+            repository
+                .transaction()
+                .write("c.txt", "c")
+                .commit()
+                .is_err()
+        );
+
+        let recovery = repository.recover(Duration::ZERO)?;
+
+        assert!(recovery.rolled_back_transaction);
+
+        assert!(fs::read_to_string(temp_path.join("a.txt"))? == "a");
+
+        assert!(!temp_path.join("b.txt").exists());
+
+        assert!(repository.find_last_commit()?.id() == head);
+
+        // a journal left after the commit went through is just removed
+        begin_transaction(&repository, &["a.txt"])?;
+
+        fs::write(temp_path.join("a.txt"), "committed")?;
+
+        repository.commit()?;
+
+        let recovery = repository.recover(Duration::ZERO)?;
+
+        assert!(!recovery.rolled_back_transaction);
+
+        assert!(fs::read_to_string(temp_path.join("a.txt"))? == "committed");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Repository, Result};
+use std::fs;
+use std::path::PathBuf;
+
+// backups of everything a transaction touches, kept until it commits
+// so recover can roll back a transaction interrupted by a crash
+pub const TRANSACTION_JOURNAL: &str = "GIT2KIT_TRANSACTION";
+
+const MANIFEST: &str = "manifest";
+const INDEX_BACKUP: &str = "index";
+const HEAD_BACKUP: &str = "HEAD";
+const FILES: &str = "files";
+
+fn journal_dir(repository: &Repository) -> PathBuf {
+    repository.repo.path().join(TRANSACTION_JOURNAL)
+}
+
+fn workdir(repository: &Repository) -> Result<PathBuf> {
+    match repository.repo.workdir() {
+        Some(workdir) => Ok(workdir.to_path_buf()),
+        None => Err(crate::Error::from_message("repository has no working tree")),
+    }
+}
+
+fn head_oid(repository: &Repository) -> String {
+    match repository.find_last_commit() {
+        Ok(commit) => commit.id().to_string(),
+        Err(_) => "unborn".to_string(),
+    }
+}
+
+pub fn begin_transaction(repository: &Repository, paths: &[&str]) -> Result<()> {
+    let dir = journal_dir(repository);
+
+    if dir.exists() {
+        return Err(crate::Error::from_message(
+            "another transaction is in progress, run recover",
+        ));
+    }
+
+    let workdir = workdir(repository)?;
+
+    fs::create_dir_all(dir.join(FILES))?;
+
+    let index_path = repository.repo.path().join("index");
+
+    if index_path.exists() {
+        fs::copy(&index_path, dir.join(INDEX_BACKUP))?;
+    }
+
+    let mut manifest = String::new();
+
+    for (n, path) in paths.iter().enumerate() {
+        let file_path = workdir.join(path);
+
+        if file_path.is_file() {
+            fs::copy(&file_path, dir.join(FILES).join(n.to_string()))?;
+        }
+
+        manifest.push_str(&format!("{} {}\n", n, path));
+    }
+
+    fs::write(dir.join(HEAD_BACKUP), head_oid(repository))?;
+
+    // written last, a journal without a manifest was never started
+    fs::write(dir.join(MANIFEST), manifest)?;
+
+    Ok(())
+}
+
+pub fn end_transaction(repository: &Repository) -> Result<()> {
+    let dir = journal_dir(repository);
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+
+    Ok(())
+}
+
+// returns whether anything was rolled back
+pub fn rollback_transaction(repository: &Repository) -> Result<bool> {
+    let dir = journal_dir(repository);
+
+    let manifest = match fs::read_to_string(dir.join(MANIFEST)) {
+        Ok(manifest) => manifest,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            end_transaction(repository)?;
+            return Ok(false);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    // the commit went through before the journal was removed
+    if fs::read_to_string(dir.join(HEAD_BACKUP))? != head_oid(repository) {
+        end_transaction(repository)?;
+        return Ok(false);
+    }
+
+    let workdir = workdir(repository)?;
+
+    for line in manifest.lines() {
+        let (n, path) = match line.split_once(' ') {
+            Some(entry) => entry,
+            None => {
+                return Err(crate::Error::from_message(
+                    "transaction journal is malformed",
+                ));
+            }
+        };
+
+        let backup = dir.join(FILES).join(n);
+
+        let file_path = workdir.join(path);
+
+        if backup.exists() {
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&backup, &file_path)?;
+        } else if file_path.is_file() {
+            fs::remove_file(&file_path)?;
+        }
+    }
+
+    let index_path = repository.repo.path().join("index");
+
+    if dir.join(INDEX_BACKUP).exists() {
+        fs::copy(dir.join(INDEX_BACKUP), &index_path)?;
+    } else if index_path.exists() {
+        fs::remove_file(&index_path)?;
+    }
+
+    // drop the index cached by the handle
+    repository.repo.index()?.read(true)?;
+
+    end_transaction(repository)?;
+
+    log::debug!("rolled back transaction");
+
+    Ok(true)
+}
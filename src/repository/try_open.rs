@@ -5,7 +5,13 @@ pub fn try_open(path: &Path) -> Result<Option<Repository>> {
     match git2::Repository::open(path) {
         Ok(repo) => {
             log::debug!("opened repo at `{}`", path.display());
-            Ok(Some(Repository { repo }))
+            Ok(Some(Repository {
+                repo,
+                merge_drivers: Default::default(),
+                filters: Default::default(),
+                lfs_store: None,
+                settings: Default::default(),
+            }))
         }
         Err(err)
             if err.class() == git2::ErrorClass::Repository
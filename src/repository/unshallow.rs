@@ -0,0 +1,202 @@
+use super::fetch::{FetchOptions, fetch};
+use crate::{FetchOutcome, Repository, Result, Settings};
+
+// a repository with full history is left as it is
+pub fn unshallow(
+    repository: &Repository,
+    settings: &Settings,
+    remote: git2::Remote,
+) -> Result<FetchOutcome> {
+    if !repository.repo.is_shallow() {
+        log::debug!("unshallow: repository has full history");
+        return Ok(FetchOutcome::default());
+    }
+
+    let options = FetchOptions {
+        depth: Some(u32::MAX),
+        ..Default::default()
+    };
+
+    fetch(repository, settings, remote, &options)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FetchOptions, Origin, PullOptions, Repository, Result, Settings};
+    use std::fs;
+    use std::net::{TcpListener, TcpStream};
+    use std::path::Path;
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+    use temp_dir::TempDir;
+
+    // serves the repositories in a directory over git://, which fetches
+    // shallow unlike the local transport, until dropped
+    struct GitDaemon {
+        child: Child,
+        port: u16,
+    }
+
+    impl GitDaemon {
+        // None if git daemon is not installed or cannot listen on loopback,
+        // the tests that need it are skipped then
+        fn start(base_path: &Path) -> Result<Option<Self>> {
+            let port = match TcpListener::bind("127.0.0.1:0") {
+                Ok(listener) => listener.local_addr()?.port(),
+                Err(err) => {
+                    eprintln!("skipping, cannot listen on loopback: {}", err);
+                    return Ok(None);
+                }
+            };
+
+            let child = Command::new("git")
+                .arg("daemon")
+                .arg("--export-all")
+                .arg("--reuseaddr")
+                .arg("--listen=127.0.0.1")
+                .arg(format!("--port={}", port))
+                .arg(format!("--base-path={}", base_path.display()))
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+
+            let child = match child {
+                Ok(child) => child,
+                Err(err) => {
+                    eprintln!("skipping, cannot run git: {}", err);
+                    return Ok(None);
+                }
+            };
+
+            let mut daemon = GitDaemon { child, port };
+
+            for _ in 0..100 {
+                if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                    return Ok(Some(daemon));
+                }
+
+                if let Some(status) = daemon.child.try_wait()? {
+                    eprintln!("skipping, git daemon exited with {}", status);
+                    return Ok(None);
+                }
+
+                std::thread::sleep(Duration::from_millis(50));
+            }
+
+            Err(crate::Error::from_message("git daemon did not start"))
+        }
+    }
+
+    impl Drop for GitDaemon {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    // commits reachable from HEAD, up to the shallow boundary
+    fn history(repository: &Repository) -> Result<usize> {
+        let mut walk = repository.repo.revwalk()?;
+
+        walk.push_head()?;
+
+        Ok(walk.count())
+    }
+
+    #[test]
+    fn unshallow_test() -> Result<()> {
+        // create a temporary directory, will be deleted by destructor
+        // must assign to keep in scope;
+        let base_dir = TempDir::new();
+
+        // reference temp_dir to not move it out of scope
+        let base_path = base_dir.as_ref().unwrap().path().to_path_buf();
+
+        let origin_path = base_path.join("origin");
+
+        let origin_repository = Repository::init(&origin_path)?;
+
+        for content in ["first", "second", "third"] {
+            fs::write(origin_path.join("foo.txt"), content)?;
+
+            origin_repository.commit()?;
+        }
+
+        let daemon = match GitDaemon::start(&base_path)? {
+            Some(daemon) => daemon,
+            None => return Ok(()),
+        };
+
+        let origin = Origin::new(&format!("git://127.0.0.1:{}/origin", daemon.port), None);
+
+        let shallow_dir = TempDir::new();
+
+        let shallow_path = shallow_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::shallow_clone(shallow_path.clone(), &origin, 1)?;
+
+        assert!(repository.is_shallow());
+
+        assert!(history(&repository)? == 1);
+
+        let (status, _) = repository.offline_status(&Settings::default())?;
+
+        assert!(status.shallow);
+
+        // pulls fetch as deep as they are told, deep enough here to reach
+        // the commit checked out
+        fs::write(origin_path.join("foo.txt"), "fourth")?;
+
+        origin_repository.commit()?;
+
+        repository.pull(
+            &origin,
+            &PullOptions {
+                depth: Some(2),
+                ..Default::default()
+            },
+        )?;
+
+        assert!(fs::read_to_string(shallow_path.join("foo.txt"))? == "fourth");
+
+        assert!(repository.is_shallow());
+
+        assert!(history(&repository)? == 2);
+
+        // fetches deepen the history
+        repository.fetch(
+            &origin,
+            &FetchOptions {
+                depth: Some(3),
+                ..Default::default()
+            },
+        )?;
+
+        assert!(repository.is_shallow());
+
+        assert!(history(&repository)? == 3);
+
+        repository.unshallow(&origin)?;
+
+        assert!(!repository.is_shallow());
+
+        assert!(history(&repository)? == 4);
+
+        let (status, _) = repository.offline_status(&Settings::default())?;
+
+        assert!(!status.shallow);
+
+        // full history has nothing to unshallow
+        let full_dir = TempDir::new();
+
+        let full_path = full_dir.as_ref().unwrap().path().to_path_buf();
+
+        let repository = Repository::clone(full_path, &origin)?;
+
+        assert!(!repository.is_shallow());
+
+        assert!(repository.unshallow(&origin)?.is_up_to_date());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use crate::{Repository, Result};
+
+// resets the index entries of paths to HEAD, leaving the working tree alone
+pub fn unstage(repository: &Repository, paths: &[&str]) -> Result<()> {
+    // before the first commit unstaging removes the paths from the index
+    let head = match repository.find_last_commit() {
+        Ok(commit) => Some(commit.into_object()),
+        Err(_) => None,
+    };
+
+    repository.repo.reset_default(head.as_ref(), paths)?;
+
+    Ok(())
+}
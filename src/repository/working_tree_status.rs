@@ -1,3 +1,4 @@
+use super::filters::is_unchanged_after_clean;
 use crate::{working_tree_status::WorkingTreeStatus, Repository, Result};
 
 pub fn working_tree_status(repository: &Repository) -> Result<WorkingTreeStatus> {
@@ -25,7 +26,14 @@ pub fn working_tree_status(repository: &Repository) -> Result<WorkingTreeStatus>
         | git2::Status::CONFLICTED;
 
     for entry in statuses.iter() {
-        let status = entry.status();
+        let mut status = entry.status();
+
+        // a smudged file differs from what is staged without being modified
+        if status.contains(git2::Status::WT_MODIFIED)
+            && is_unchanged_after_clean(repository, &String::from_utf8_lossy(entry.path_bytes()))?
+        {
+            status.remove(git2::Status::WT_MODIFIED);
+        }
 
         result.working_changed |= status.intersects(working_changed_mask);
         result.index_changed |= status.intersects(index_changed_mask);
@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+// the operation git2kit or the user left unfinished in the repository
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryState {
+    Clean,
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+    ApplyMailbox,
+}
+
+impl From<git2::RepositoryState> for RepositoryState {
+    fn from(state: git2::RepositoryState) -> RepositoryState {
+        match state {
+            git2::RepositoryState::Clean => RepositoryState::Clean,
+            git2::RepositoryState::Merge => RepositoryState::Merge,
+            git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::ApplyMailboxOrRebase => RepositoryState::Rebase,
+            git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
+                RepositoryState::CherryPick
+            }
+            git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => {
+                RepositoryState::Revert
+            }
+            git2::RepositoryState::Bisect => RepositoryState::Bisect,
+            git2::RepositoryState::ApplyMailbox => RepositoryState::ApplyMailbox,
+        }
+    }
+}
+
+impl RepositoryState {
+    pub fn is_clean(&self) -> bool {
+        matches!(self, RepositoryState::Clean)
+    }
+}
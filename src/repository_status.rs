@@ -1,5 +1,6 @@
 use super::{
-    head_status::HeadStatus, repository::UpstreamStatus, working_tree_status::WorkingTreeStatus,
+    head_status::HeadStatus, repository::UpstreamStatus, repository_state::RepositoryState,
+    working_tree_status::WorkingTreeStatus,
 };
 use serde::Serialize;
 
@@ -9,6 +10,11 @@ pub struct RepositoryStatus {
     pub upstream: UpstreamStatus,
     pub working_tree: WorkingTreeStatus,
     pub default_branch: Option<String>,
+    pub state: RepositoryState,
+    // paths with unresolved conflicts in the index
+    pub conflicts: Vec<String>,
+    // history was cut off by a shallow clone or fetch
+    pub shallow: bool,
 }
 
 impl RepositoryStatus {
@@ -18,4 +24,8 @@ impl RepositoryStatus {
             None => false,
         }
     }
+
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
 }
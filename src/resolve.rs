@@ -4,11 +4,25 @@ use serde::{Deserialize, Serialize};
 #[serde(deny_unknown_fields, rename_all = "kebab-case")]
 pub struct Resolve {
     pub ok: bool,
-    // TODO hunks
+    // files left conflicted when ok is false
+    #[serde(default)]
+    pub conflicts: Vec<ResolveConflict>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ResolveConflict {
+    pub path: String,
+    // reported by the merge driver, such as csv keys changed differently on
+    // both sides, empty if the whole file conflicts
+    pub keys: Vec<String>,
 }
 
 impl Resolve {
     pub fn new(ok: bool) -> Self {
-        Resolve { ok: true }
+        Resolve {
+            ok,
+            conflicts: vec![],
+        }
     }
 }
@@ -10,6 +10,10 @@ pub struct Settings {
     pub editor: Option<String>,
     pub ignore: Option<bool>,
     pub prune: Option<bool>,
+    // also delete local tags missing on the remote when pruning
+    pub prune_tags: Option<bool>,
+    #[serde(default)]
+    pub encryption: Vec<EncryptionSettings>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone)]
@@ -19,3 +23,12 @@ pub struct SshSettings {
     pub public_key_path: Option<PathBuf>,
     pub private_key_path: PathBuf,
 }
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct EncryptionSettings {
+    // files to encrypt, such as `personal/*.csv`
+    pub patterns: Vec<String>,
+    // 256-bit key as 64 hex digits
+    pub key: String,
+}
@@ -0,0 +1,17 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Stash {
+    // position in the stash list, 0 is the most recent
+    pub index: usize,
+    pub message: String,
+    pub oid: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "state", content = "paths", rename_all = "snake_case")]
+pub enum StashOutcome {
+    Applied,
+    // the stash is kept and the paths are left with conflict markers
+    Conflicted(Vec<String>),
+}
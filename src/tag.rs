@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    // the object the tag points to, usually a commit
+    pub target: String,
+    // None for lightweight tags
+    pub message: Option<String>,
+}
+
+impl Tag {
+    pub fn is_annotated(&self) -> bool {
+        self.message.is_some()
+    }
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TreeEntry {
+    // relative to the root of the repository
+    pub path: String,
+    pub kind: TreeEntryKind,
+    // git file mode, such as 0o100644 or 0o040000
+    pub mode: i32,
+    pub oid: String,
+    // None for trees and submodules
+    pub size: Option<usize>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeEntryKind {
+    Blob,
+    Tree,
+    Submodule,
+}